@@ -0,0 +1,170 @@
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use crate::{
+  BoardScale, BoardSnapshot, Direction, GridStyle, TileActionKind,
+  board::CoversGrid, style,
+};
+
+/// Holds the merge badges on the edges of the grid. Only spawned while
+/// [`GridStyle::merge_badges`] is on.
+#[derive(Component)]
+pub(crate) struct MergeBadges;
+
+const DIMMED: f32 = 0.35;
+
+/// Respawns the badges for the board as shown, or only despawns them if
+/// [`GridStyle::merge_badges`] is off.
+pub(crate) fn update_merge_badges(
+  grid_style: Res<GridStyle>,
+  scale: Res<BoardScale>,
+  snapshot: BoardSnapshot,
+  badges: Option<Single<Entity, With<MergeBadges>>>,
+  mut commands: Commands,
+) {
+  if let Some(badges) = badges {
+    commands.entity(*badges).despawn();
+  }
+  if !grid_style.merge_badges {
+    return;
+  }
+  let board = snapshot.board();
+  let shiftability = board.shiftability();
+  let badges = Direction::ALL.map(|dir| {
+    let merges = board
+      .peek_shift(dir)
+      .into_iter()
+      .filter(|a| a.kind == TileActionKind::Merge)
+      .count();
+    badge(dir, merges, shiftability.can(dir))
+  });
+  let mut node = Node::default();
+  scale.layout_cover(&mut node);
+  commands.spawn((
+    MergeBadges,
+    CoversGrid,
+    Name::new("overlay-badges"),
+    node,
+    GlobalZIndex(1),
+    Children::spawn(SpawnIter(badges.into_iter())),
+  ));
+}
+
+/// Returns a badge centered on the grid's frame at the `direction` edge.
+fn badge(direction: Direction, merges: usize, legal: bool) -> impl Bundle {
+  // the frame takes 3% of the grid on every side
+  let (edge, across) = (Val::Percent(0.0), Val::Percent(3.0));
+  let mut node = Node {
+    position_type: PositionType::Absolute,
+    justify_content: JustifyContent::Center,
+    align_items: AlignItems::Center,
+    ..default()
+  };
+  match direction {
+    Direction::Up | Direction::Down => {
+      (node.left, node.right, node.height) = (edge, edge, across);
+    }
+    Direction::Left | Direction::Right => {
+      (node.top, node.bottom, node.width) = (edge, edge, across);
+    }
+  }
+  match direction {
+    Direction::Up => node.top = edge,
+    Direction::Down => node.bottom = edge,
+    Direction::Left => node.left = edge,
+    Direction::Right => node.right = edge,
+  }
+  let name = format!("badge-{direction:?}").to_lowercase();
+  let color = if legal {
+    style::TEXT_LIGHT
+  } else {
+    style::TEXT_LIGHT.with_alpha(DIMMED)
+  };
+  (
+    Name::new(name),
+    node,
+    children![(
+      Text::new(merges.to_string()),
+      TextColor(color),
+      style::BADGE_FONT,
+    )],
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::state::app::StatesPlugin;
+
+  use super::*;
+  use crate::{Board, BoardPlugin, board::BoardRes, find_by_name};
+
+  fn badges(app: &mut App) -> Vec<(Direction, String, bool)> {
+    Direction::ALL
+      .into_iter()
+      .map(|dir| {
+        let name = format!("badge-{dir:?}").to_lowercase();
+        let badge = find_by_name(app.world_mut(), &name).unwrap();
+        let text = app.world().get::<Children>(badge).unwrap()[0];
+        let text = app.world().entity(text);
+        let legal = text.get::<TextColor>().unwrap().0.alpha() == 1.0;
+        (dir, text.get::<Text>().unwrap().0.clone(), legal)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn merge_counts() {
+    use Direction::*;
+
+    let mut app = App::new();
+    app.add_plugins((
+      MinimalPlugins,
+      StatesPlugin,
+      BoardPlugin::default().without_input(),
+    ));
+    app.update();
+    let shown =
+      |app: &mut App| find_by_name(app.world_mut(), "overlay-badges").is_some();
+    assert!(!shown(&mut app));
+
+    app.world_mut().resource_mut::<GridStyle>().merge_badges = true;
+    app.world_mut().resource_mut::<BoardRes>().0 = Board([
+      [1, 1, 2, 2], //
+      [3, 0, 0, 3],
+      [4, 0, 0, 0],
+      [4, 0, 0, 0],
+    ]);
+    app.update();
+    assert!(shown(&mut app));
+    assert_eq!(
+      badges(&mut app),
+      vec![
+        (Up, "1".into(), true),
+        (Down, "1".into(), true),
+        (Left, "3".into(), true),
+        (Right, "3".into(), true),
+      ]
+    );
+
+    // a board stuck to the top left can't go up or left
+    app.world_mut().resource_mut::<BoardRes>().0 = Board([
+      [1, 2, 0, 0], //
+      [2, 1, 0, 0],
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+    ]);
+    app.update();
+    assert_eq!(
+      badges(&mut app),
+      vec![
+        (Up, "0".into(), false),
+        (Down, "0".into(), true),
+        (Left, "0".into(), false),
+        (Right, "0".into(), true),
+      ]
+    );
+
+    app.world_mut().resource_mut::<GridStyle>().merge_badges = false;
+    app.update();
+    assert!(!shown(&mut app));
+  }
+}
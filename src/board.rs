@@ -1,136 +1,687 @@
+use std::time::Duration;
+
 use bevy::{
   app::Plugin,
-  ecs::{
-    relationship::RelatedSpawner,
-    spawn::{SpawnIter, SpawnWith},
-  },
+  ecs::{relationship::RelatedSpawner, spawn::SpawnWith, system::SystemParam},
+  input::mouse::{AccumulatedMouseScroll, MouseScrollUnit},
   prelude::*,
+  ui::RelativeCursorPosition,
+  window::PrimaryWindow,
 };
+use rand::{SeedableRng, rngs::StdRng};
 
 use crate::{
-  AppState,
-  domain::{Board, Direction, TileAction, TileActionKind},
-  style,
+  AppState, badges,
+  domain::{
+    Board, Direction, InitialLayout, SpawnBias, TileAction, TileActionKind,
+  },
+  input::{
+    GameAction, GestureSettings, KeyBindings, Modifiers, classify_swipe,
+  },
+  overlay,
+  rules::{GameOverReason, GameOverRules, MoveCounters, WinTarget},
+  stats::DirectionStats,
+  style::{self, ColorTween, GridStyle},
 };
 
-pub struct BoardPlugin;
+/// Spawns the board and drives the game on it.
+///
+/// By default the plugin handles keyboard, mouse and touch input and shows the
+/// game over and win overlays. Either can be left out to be replaced by the
+/// app, with [`GameAction`] events and [`AppState`] transitions serving as the
+/// seams.
+pub struct BoardPlugin {
+  input: bool,
+  overlays: bool,
+}
+
+impl Default for BoardPlugin {
+  fn default() -> Self {
+    Self {
+      input: true,
+      overlays: true,
+    }
+  }
+}
+
+impl BoardPlugin {
+  /// Leaves out input handling. The game is then only driven by
+  /// [`GameAction`] events written by the app.
+  pub fn without_input(mut self) -> Self {
+    self.input = false;
+    self
+  }
+
+  /// Leaves out the game over and win overlays. [`AppState::GameOver`] and
+  /// [`AppState::Won`] are still entered, but nothing is shown for them.
+  pub fn without_overlays(mut self) -> Self {
+    self.overlays = false;
+    self
+  }
+}
 
 impl Plugin for BoardPlugin {
   fn build(&self, app: &mut App) {
     app
+      .init_state::<AppState>()
       .insert_resource(BoardRes(Board::empty()))
-      .add_event::<BoardShifted>()
+      .init_resource::<GridStyle>()
+      .init_resource::<BoardScale>()
+      .init_resource::<StartingLayout>()
+      .init_resource::<DirectionStats>()
+      .init_resource::<GameOverRules>()
+      .init_resource::<MoveCounters>()
+      .init_resource::<WinTarget>()
+      .add_event::<GameAction>()
       .add_event::<TileAnimated>()
+      .register_type::<BoardRes>()
+      .register_type::<GridStyle>()
+      .register_type::<BoardScale>()
+      .register_type::<StartingLayout>()
+      .register_type::<DirectionStats>()
+      .register_type::<GameOverRules>()
+      .register_type::<WinTarget>()
+      .register_type::<Grid>()
+      .register_type::<Tile>()
+      .register_type::<Animation>()
+      .configure_sets(
+        Update,
+        (BoardSet::Shift, BoardSet::Animate, BoardSet::Commit).chain(),
+      )
       .add_systems(Startup, setup)
-      .add_systems(OnEnter(AppState::Playing), restart)
+      .add_systems(
+        OnEnter(AppState::Playing),
+        restart.run_if(not(resource_exists::<TargetReached>)),
+      )
+      .add_systems(OnExit(AppState::GameOver), forget_win)
       .add_systems(
         Update,
-        (handle_input, shift_board, assign_animations)
+        (shift_board, assign_animations, start_nudge)
           .chain()
           .run_if(player_can_interact())
-          .before(animate_tiles),
+          .in_set(BoardSet::Shift),
       )
-      .add_systems(Update, animate_tiles.run_if(animating))
+      .add_systems(
+        Update,
+        (animate_tiles, pop_tiles)
+          .chain()
+          .run_if(animating)
+          .in_set(BoardSet::Animate),
+      )
+      .add_systems(
+        Update,
+        style::tween_colors
+          .after(pop_tiles)
+          .in_set(BoardSet::Animate),
+      )
+      .add_systems(Update, nudge_grid)
       .add_systems(
         Update,
         (
-          redraw_board.run_if(on_event::<BoardShifted>),
-          check_game_over,
+          redraw_board.run_if(
+            resource_exists::<PendingMove>.or(resource_changed::<GridStyle>),
+          ),
+          (
+            check_game_over,
+            check_win.run_if(not(resource_exists::<TargetReached>)),
+          )
+            .chain()
+            .run_if(not(resource_exists::<PendingMove>)),
         )
           .chain()
           .run_if(player_can_interact())
-          .after(animate_tiles),
+          .in_set(BoardSet::Commit),
+      )
+      .add_systems(Update, draw_board_views.after(BoardSet::Commit))
+      .add_systems(
+        Update,
+        badges::update_merge_badges
+          .run_if(
+            resource_changed::<BoardRes>
+              .or(resource_removed::<PendingMove>)
+              .or(resource_changed::<GridStyle>),
+          )
+          .after(BoardSet::Commit),
+      )
+      .add_systems(
+        Update,
+        rescale_board
+          .run_if(resource_changed::<BoardScale>)
+          .before(BoardSet::Animate),
+      )
+      .add_systems(Update, apply_restart.before(BoardSet::Shift))
+      .add_systems(
+        Update,
+        apply_keep_going
+          .run_if(in_state(AppState::Won))
+          .before(BoardSet::Shift),
       );
+    if self.input {
+      app
+        .init_resource::<InputDebounce>()
+        .init_resource::<KeyBindings>()
+        .init_resource::<GestureSettings>()
+        .add_systems(
+          Update,
+          (
+            (handle_input, handle_swipes).run_if(player_can_interact()),
+            overlay::handle_restart.run_if(in_state(AppState::GameOver)),
+            overlay::handle_keep_going.run_if(in_state(AppState::Won)),
+          )
+            .before(apply_restart)
+            .before(apply_keep_going),
+        )
+        .add_systems(Update, scale_with_wheel.before(rescale_board));
+    }
+    if self.overlays {
+      app
+        .add_systems(
+          OnEnter(AppState::GameOver),
+          overlay::show_game_over_overlay,
+        )
+        .add_systems(
+          OnExit(AppState::GameOver),
+          overlay::hide_game_over_overlay,
+        )
+        .add_systems(OnEnter(AppState::Won), overlay::show_win_overlay)
+        .add_systems(OnExit(AppState::Won), overlay::hide_win_overlay);
+    }
   }
 }
 
-const SIZE: usize = 4;
+/// Stages of a move, run in order every frame.
+///
+/// [`BoardSet::Shift`] applies a requested move to the board right away, but
+/// the tiles on screen only catch up once [`BoardSet::Commit`] redraws them
+/// after the animations, which can take several frames. Until then the move
+/// is kept in [`PendingMove`]. Systems that need the board as the player sees
+/// it should read it through a [`BoardSnapshot`].
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BoardSet {
+  /// Applies a [`GameAction::Shift`] and starts the animations.
+  Shift,
+  /// Advances the animations.
+  Animate,
+  /// Redraws the board once nothing is animating and checks for game over.
+  Commit,
+}
+
+const ROWS: usize = 4;
+const COLS: usize = 4;
 
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub(crate) struct BoardRes(pub(crate) Board<ROWS, COLS>);
+
+/// A move already applied to the board but not yet shown on screen. Present
+/// from [`BoardSet::Shift`] until [`BoardSet::Commit`] redraws the board.
+#[derive(Resource, Clone, Debug)]
+pub struct PendingMove {
+  pub direction: Direction,
+  pub actions: Vec<TileAction>,
+  /// The board as it was before the move, and as it's still drawn.
+  pub before: Board<ROWS, COLS>,
+}
+
+/// The board as currently shown on screen, which lags behind the game's
+/// board while a move is animating.
+#[derive(SystemParam)]
+pub struct BoardSnapshot<'w> {
+  board: Res<'w, BoardRes>,
+  pending: Option<Res<'w, PendingMove>>,
+}
+
+impl BoardSnapshot<'_> {
+  /// Returns the board as currently drawn.
+  pub fn board(&self) -> &Board<ROWS, COLS> {
+    match &self.pending {
+      Some(pending) => &pending.before,
+      None => &self.board.0,
+    }
+  }
+
+  /// Returns the move that's been applied but not yet drawn, if any.
+  pub fn pending(&self) -> Option<&PendingMove> {
+    self.pending.as_deref()
+  }
+}
+
+/// Tiles every new game starts with, e.g.
+/// `StartingLayout(InitialLayout::Random(1))` for variants starting with a
+/// single tile.
+#[derive(Resource, Default, Clone, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct StartingLayout(pub InitialLayout);
+
+/// Quietly helps a struggling player: once the board's
+/// [difficulty estimate](Board::difficulty_estimate) reaches `threshold`, new
+/// tiles spawn where `evaluate` rates the board best. The assisted mode is on
+/// while this resource exists, and the game over overlay says so.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct SpawnAssist {
+  pub threshold: f32,
+  /// Rates a board after a spawn, the higher the better for the player. An
+  /// app with a solver can plug it in here.
+  pub evaluate: fn(&Board<ROWS, COLS>) -> f32,
+}
+
+impl Default for SpawnAssist {
+  fn default() -> Self {
+    Self {
+      threshold: 0.8,
+      evaluate: |board| -board.difficulty_estimate(),
+    }
+  }
+}
+
+/// Makes the game as hard as it gets, like "evil 2048": new tiles spawn
+/// where they hurt the most, as `evaluate` rates the board after the
+/// player's [best reply](Board::best_reply) `depth` moves ahead. Takes
+/// precedence over [`SpawnAssist`], and the game over overlay says it's on.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct SpawnAdversary {
+  /// Clamped to [`MAX_DEPTH`](Self::MAX_DEPTH).
+  pub depth: u8,
+  /// Rates a board, the higher the better for the player.
+  pub evaluate: fn(&Board<ROWS, COLS>) -> f32,
+}
+
+impl SpawnAdversary {
+  /// The deepest lookahead still picking a spawn on a 4x4 board in a few
+  /// milliseconds, so a move never stalls.
+  pub const MAX_DEPTH: u8 = 2;
+}
+
+impl Default for SpawnAdversary {
+  fn default() -> Self {
+    Self {
+      depth: 1,
+      evaluate: |board| -board.difficulty_estimate(),
+    }
+  }
+}
+
+/// A board the next game starts with instead of a freshly spawned one.
 #[derive(Resource)]
-struct BoardRes(Board<SIZE>);
+pub(crate) struct InitialBoard(pub(crate) Board<ROWS, COLS>);
 
-#[derive(Component)]
+impl InitialBoard {
+  /// Reads the board from a `--board <code>` argument or the `BOARD`
+  /// environment variable. See [`Board::from_code`] for the format.
+  pub(crate) fn from_env() -> Option<Self> {
+    let code = arg_or_env("--board", "BOARD")?;
+    let board = Board::from_code(&code);
+    if board.is_none() {
+      warn!("ignoring invalid board code {code:?}");
+    }
+    board.map(Self)
+  }
+}
+
+/// Rolls the tiles spawned on a standard new board and after every move, so
+/// a seeded one spawns the same tiles for the same moves. Spawns are rolled
+/// from the thread's generator without it.
+#[derive(Resource)]
+pub(crate) struct SpawnRng(pub(crate) StdRng);
+
+impl SpawnRng {
+  /// Reads the seed from a `--seed <number>` argument or the `SEED`
+  /// environment variable.
+  pub(crate) fn from_env() -> Option<Self> {
+    let seed = arg_or_env("--seed", "SEED")?;
+    let rng = seed.trim().parse().map(StdRng::seed_from_u64);
+    if rng.is_err() {
+      warn!("ignoring invalid seed {seed:?}");
+    }
+    rng.ok().map(Self)
+  }
+}
+
+/// Returns the argument following `flag` on the command line, or else the
+/// `var` environment variable.
+fn arg_or_env(flag: &str, var: &str) -> Option<String> {
+  std::env::args()
+    .skip_while(|arg| arg != flag)
+    .nth(1)
+    .or_else(|| std::env::var(var).ok())
+}
+
+#[derive(Component, Default, Reflect)]
+#[reflect(Component, Default)]
 struct Grid;
 
-#[derive(Component)]
+/// Draws a board inside a UI node, e.g. a preview of a board that isn't being
+/// played. The node's tiles are rebuilt whenever the view's board or the
+/// [`GridStyle`] changes, so every view looks like the live game, which is
+/// drawn through one too. The tiles of a view with a [`Name`] are named after
+/// it, e.g. `preview-tile-r2c3`.
+#[derive(Component, Clone, PartialEq, Debug)]
+#[require(
+  Node = BoardView::node(),
+  BorderColor(style::GRID),
+  BackgroundColor(style::GRID)
+)]
+pub struct BoardView(pub Board<ROWS, COLS>);
+
+impl BoardView {
+  /// Returns the layout of a view filling its parent's width.
+  fn node() -> Node {
+    Node {
+      width: Val::Percent(100.0),
+      aspect_ratio: Some(COLS as f32 / ROWS as f32),
+      display: Display::Grid,
+      grid_template_columns: RepeatedGridTrack::flex(COLS as u16, 1.0),
+      grid_template_rows: RepeatedGridTrack::flex(ROWS as u16, 1.0),
+      border: UiRect::all(Val::Percent(3.0)),
+      row_gap: Val::Percent(3.0),
+      column_gap: Val::Percent(3.0),
+      ..default()
+    }
+  }
+}
+
+/// How much of the window's smaller side the grid takes up, between
+/// [`MIN`](Self::MIN) and [`MAX`](Self::MAX). The grid is centered in the
+/// window, and Ctrl+scroll over it adjusts the scale.
+#[derive(Resource, Clone, Copy, PartialEq, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct BoardScale(f32);
+
+impl Default for BoardScale {
+  fn default() -> Self {
+    Self(Self::MAX)
+  }
+}
+
+impl BoardScale {
+  pub const MIN: f32 = 0.5;
+  pub const MAX: f32 = 1.0;
+  /// How much a line of Ctrl+scroll changes the scale.
+  const STEP: f32 = 0.05;
+
+  /// Creates a scale, clamped to the allowed range.
+  pub fn new(scale: f32) -> Self {
+    Self(scale.clamp(Self::MIN, Self::MAX))
+  }
+
+  pub fn get(self) -> f32 {
+    self.0
+  }
+
+  /// Returns `vmin` percent of the window's smaller side, scaled.
+  pub(crate) fn vmin(self, vmin: f32) -> Val {
+    Val::VMin(vmin * self.0)
+  }
+
+  /// Sizes the grid's `node`. Tiles are laid out and slide relative to the
+  /// grid, so this is all it takes to rescale the board, even mid-move.
+  fn layout_grid(self, node: &mut Node) {
+    node.max_width = self.grid_width();
+    // the frame is a border rather than padding so it can keep the grid's
+    // color when gaps are drawn in another one
+    node.border = UiRect::all(self.vmin(3.0));
+    node.row_gap = self.vmin(3.0);
+    node.column_gap = self.vmin(3.0);
+    node.margin = UiRect::all(Val::Auto);
+  }
+
+  /// Sizes the `node` of a [`CoversGrid`] overlay to the grid's size and
+  /// centers it, as the grid is.
+  pub(crate) fn layout_cover(self, node: &mut Node) {
+    node.width = Val::Percent(100.0);
+    node.max_width = self.grid_width();
+    node.aspect_ratio = Some(COLS as f32 / ROWS as f32);
+    node.margin = UiRect::all(Val::Auto);
+  }
+
+  /// Returns the width of the grid, frame included.
+  fn grid_width(self) -> Val {
+    // tall boards are bound by the window's height rather than width
+    self.vmin(100.0 * (COLS as f32 / ROWS as f32).min(1.0))
+  }
+}
+
+/// A root node laid over the grid. It has to be as wide as the grid and
+/// centered, and is kept to the grid's size as the [`BoardScale`] changes.
+#[derive(Component, Default)]
+pub(crate) struct CoversGrid;
+
+#[derive(Component, Default, Reflect)]
+#[reflect(Component, Default)]
 struct Tile;
 
-#[derive(Component)]
-enum Animation {
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub(crate) enum Animation {
   Move {
     dir: Direction,
     tiles_to_move: f32,
     tiles_to_move_left: f32, // zero when animation is finished
   },
   Merge {
+    into: Entity,
     value: u8,
     dir: Direction,
-    tiles_to_move: f32,      // zero when animation is finished
+    tiles_to_move: f32,
     tiles_to_move_left: f32, // zero when animation is finished
   },
 }
 
-#[derive(Event)]
-struct BoardShifted(Direction);
+#[cfg(all(feature = "debug-ui", debug_assertions))]
+impl std::fmt::Display for Animation {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let (kind, dir, tiles_to_move, tiles_to_move_left) = match self {
+      Animation::Move {
+        dir,
+        tiles_to_move,
+        tiles_to_move_left,
+      } => ("Move", dir, tiles_to_move, tiles_to_move_left),
+      Animation::Merge {
+        dir,
+        tiles_to_move,
+        tiles_to_move_left,
+        ..
+      } => ("Merge", dir, tiles_to_move, tiles_to_move_left),
+    };
+    let progress = 1.0 - tiles_to_move_left / tiles_to_move;
+    write!(f, "{kind} {dir:?} {:.0}%", progress * 100.0)
+  }
+}
+
+/// Briefly grows and shrinks back a tile another one has merged into,
+/// swapping its number to `value` at the peak.
+#[derive(Component)]
+pub(crate) struct Pop {
+  value: u8,
+  elapsed: f32,
+  flipped: bool,
+}
+
+impl Pop {
+  const DURATION: f32 = 0.12; // s
+  const SCALE: f32 = 1.2;
+
+  fn new(value: u8) -> Self {
+    Self {
+      value,
+      elapsed: 0.0,
+      flipped: false,
+    }
+  }
+
+  /// Advances the animation by `dt` seconds. Returns the tile's scale and
+  /// whether its number should be swapped on this step.
+  fn advance(&mut self, dt: f32) -> (f32, bool) {
+    self.elapsed += dt;
+    let t = self.progress();
+    let flip = !self.flipped && t >= 0.5;
+    self.flipped |= flip;
+    let scale = 1.0 + (Self::SCALE - 1.0) * (t * std::f32::consts::PI).sin();
+    (scale, flip)
+  }
+
+  fn progress(&self) -> f32 {
+    (self.elapsed / Self::DURATION).min(1.0)
+  }
+
+  fn is_finished(&self) -> bool {
+    self.progress() >= 1.0
+  }
+}
+
+#[cfg(all(feature = "debug-ui", debug_assertions))]
+impl std::fmt::Display for Pop {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "Pop {:.0}%", self.progress() * 100.0)
+  }
+}
+
+/// Briefly pushes the grid toward the shift direction and springs it back.
+/// Moves the grid as a whole, so it doesn't interfere with tile slides.
+#[derive(Component)]
+struct Nudge {
+  dir: Direction,
+  elapsed: f32,
+}
+
+impl Nudge {
+  const DURATION: f32 = 0.15; // s
+  const DISTANCE: f32 = 6.0; // px
+
+  fn new(dir: Direction) -> Self {
+    Self { dir, elapsed: 0.0 }
+  }
+
+  /// Returns how far the grid is pushed `elapsed` seconds into the nudge.
+  fn offset(elapsed: f32) -> f32 {
+    let t = (elapsed / Self::DURATION).clamp(0.0, 1.0);
+    Self::DISTANCE * (t * std::f32::consts::PI).sin()
+  }
+
+  fn is_finished(&self) -> bool {
+    self.elapsed >= Self::DURATION
+  }
+}
+
+/// Ignores a press of the same direction coming within `window` of the
+/// previously accepted one, which some keyboards produce from a single press.
+#[derive(Resource, Debug)]
+pub struct InputDebounce {
+  pub window: Duration,
+  last: Option<(Direction, Duration)>,
+}
+
+impl Default for InputDebounce {
+  fn default() -> Self {
+    Self::new(Duration::from_millis(80))
+  }
+}
+
+impl InputDebounce {
+  pub fn new(window: Duration) -> Self {
+    Self { window, last: None }
+  }
+
+  /// Returns `true` if a press of `dir` made at `now` should be accepted and
+  /// remembers it if so.
+  fn accept(&mut self, dir: Direction, now: Duration) -> bool {
+    if matches!(
+      self.last,
+      Some((last_dir, at)) if last_dir == dir && now - at < self.window
+    ) {
+      return false;
+    }
+    self.last = Some((dir, now));
+    true
+  }
+}
 
 #[derive(Event)]
 enum TileAnimated {
   Moved {
-    value: u8,
     from: (usize, usize),
     to: (usize, usize),
   },
   Merged {
-    value: u8,
+    result: u8,
     from: (usize, usize),
     at: (usize, usize),
   },
-  Spawned {
-    value: u8,
-    at: (usize, usize),
-  },
 }
 
 fn setup(mut commands: Commands) {
-  commands.spawn(Camera2d);
-  commands.run_system_cached(restart);
+  commands.spawn((Name::new("camera"), Camera2d));
 }
 
 fn restart(
   mut board_res: ResMut<BoardRes>,
+  (initial_board, spawn_rng): (
+    Option<Res<InitialBoard>>,
+    Option<ResMut<SpawnRng>>,
+  ),
+  (starting_layout, scale): (Res<StartingLayout>, Res<BoardScale>),
+  mut stats: ResMut<DirectionStats>,
+  mut counters: ResMut<MoveCounters>,
   old_grid: Query<Option<Entity>, With<Grid>>,
   mut commands: Commands,
 ) {
+  *stats = DirectionStats::default();
+  *counters = MoveCounters::default();
+  commands.remove_resource::<GameOverReason>();
+  commands.remove_resource::<TargetReached>();
+  commands.remove_resource::<PendingMove>();
   if let Ok(Some(grid)) = old_grid.single() {
     commands.entity(grid).despawn();
   }
-  let board = Board::<SIZE>::new();
-  commands.spawn(grid(&board));
+  let board = match initial_board {
+    Some(initial_board) => {
+      commands.remove_resource::<InitialBoard>();
+      initial_board.0.clone()
+    }
+    None => match (&starting_layout.0, spawn_rng) {
+      (InitialLayout::Standard, Some(mut rng)) => {
+        Board::new_with_rng(&mut rng.0)
+      }
+      (layout, _) => {
+        Board::<ROWS, COLS>::with_layout(layout).unwrap_or_else(|e| {
+          warn!("can't start with {:?}: {e}", starting_layout.0);
+          Board::new()
+        })
+      }
+    },
+  };
+  commands.spawn(grid(&board, *scale));
   board_res.0 = board;
 }
 
-fn grid(board: &Board<SIZE>) -> impl Bundle {
-  let nums = board.iter_numbers().collect::<Vec<_>>();
+fn grid(board: &Board<ROWS, COLS>, scale: BoardScale) -> impl Bundle {
+  let mut node = BoardView::node();
+  scale.layout_grid(&mut node);
   (
     Grid,
-    Node {
-      width: Val::Percent(100.0),
-      max_width: Val::VMin(100.0),
-      aspect_ratio: Some(1.0),
-      display: Display::Grid,
-      grid_template_columns: RepeatedGridTrack::flex(SIZE as u16, 1.0),
-      grid_template_rows: RepeatedGridTrack::flex(SIZE as u16, 1.0),
-      padding: UiRect::all(Val::VMin(3.0)),
-      row_gap: Val::VMin(3.0),
-      column_gap: Val::VMin(3.0),
-      ..default()
-    },
-    BackgroundColor(style::GRID),
-    Children::spawn(SpawnIter(nums.into_iter().map(tile))),
+    Name::new("grid"),
+    BoardView(board.clone()),
+    node,
+    RelativeCursorPosition::default(),
   )
 }
 
-fn tile(n: u8) -> impl Bundle {
+/// Resizes the grid and the overlays covering it to the current
+/// [`BoardScale`].
+fn rescale_board(
+  scale: Res<BoardScale>,
+  grid: Query<&mut Node, With<Grid>>,
+  overlays: Query<&mut Node, (With<CoversGrid>, Without<Grid>)>,
+) {
+  for mut node in grid {
+    scale.layout_grid(&mut node);
+  }
+  for mut node in overlays {
+    scale.layout_cover(&mut node);
+  }
+}
+
+fn tile(n: u8, shadow: bool) -> impl Bundle {
   (
     Tile,
     Node {
@@ -142,14 +693,16 @@ fn tile(n: u8) -> impl Bundle {
       ..default()
     },
     BackgroundColor(style::tile_foreground(n)),
+    if shadow && n > 0 {
+      style::tile_shadow()
+    } else {
+      BoxShadow::default()
+    },
     Children::spawn(SpawnWith(move |parent: &mut RelatedSpawner<ChildOf>| {
       if n > 0 {
         parent.spawn((
           Text::new(2u32.pow(n as u32).to_string()),
-          TextFont {
-            font_size: 56.0,
-            ..default()
-          },
+          style::TILE_FONT,
           TextColor(style::tile_text(n)),
         ));
       }
@@ -159,67 +712,218 @@ fn tile(n: u8) -> impl Bundle {
 
 fn check_game_over(
   board_res: Res<BoardRes>,
+  rules: Res<GameOverRules>,
+  counters: Res<MoveCounters>,
   mut next_state: ResMut<NextState<AppState>>,
+  mut commands: Commands,
 ) {
-  if !board_res.0.is_shiftable() {
+  if let Some(reason) = rules.check(&board_res.0, &counters) {
+    commands.insert_resource(reason);
     next_state.set(AppState::GameOver);
   }
 }
 
+/// Present once the [`WinTarget`] has been reached in the current game, so
+/// that going on playing it neither starts it over nor wins it again.
+#[derive(Resource)]
+struct TargetReached;
+
+fn check_win(
+  board_res: Res<BoardRes>,
+  target: Res<WinTarget>,
+  mut next_state: ResMut<NextState<AppState>>,
+  mut commands: Commands,
+) {
+  // a game that's over can't be won anymore
+  if matches!(*next_state, NextState::Pending(_)) {
+    return;
+  }
+  if board_res.0.has_reached(target.0) {
+    commands.insert_resource(TargetReached);
+    next_state.set(AppState::Won);
+  }
+}
+
+fn forget_win(mut commands: Commands) {
+  commands.remove_resource::<TargetReached>();
+}
+
 fn handle_input(
   keyboard_input: Res<ButtonInput<KeyCode>>,
-  mut events: EventWriter<BoardShifted>,
+  bindings: Res<KeyBindings>,
+  time: Res<Time<Real>>,
+  mut debounce: ResMut<InputDebounce>,
+  mut events: EventWriter<GameAction>,
+) {
+  for (chord, action) in bindings.actions() {
+    if !chord.just_pressed(&keyboard_input) {
+      continue;
+    }
+    match action {
+      GameAction::Restart | GameAction::KeepGoing => {
+        events.write(action);
+        return;
+      }
+      GameAction::Shift(dir) => {
+        if debounce.accept(dir, time.elapsed()) {
+          events.write(action);
+        }
+      }
+    }
+  }
+}
+
+/// Starts the game over on a [`GameAction::Restart`].
+fn apply_restart(
+  state: Res<State<AppState>>,
+  mut actions: EventReader<GameAction>,
+  mut next_state: ResMut<NextState<AppState>>,
   mut commands: Commands,
 ) {
-  if keyboard_input.just_pressed(KeyCode::KeyR) {
-    commands.run_system_cached(restart);
+  if !actions.read().any(|a| *a == GameAction::Restart) {
     return;
   }
-  for (key, dir) in [
-    (KeyCode::ArrowUp, Direction::Up),
-    (KeyCode::ArrowDown, Direction::Down),
-    (KeyCode::ArrowLeft, Direction::Left),
-    (KeyCode::ArrowRight, Direction::Right),
-    (KeyCode::KeyW, Direction::Up),
-    (KeyCode::KeyS, Direction::Down),
-    (KeyCode::KeyA, Direction::Left),
-    (KeyCode::KeyD, Direction::Right),
-  ] {
-    if keyboard_input.just_pressed(key) {
-      events.write(BoardShifted(dir));
+  match state.get() {
+    AppState::Playing => commands.run_system_cached(restart),
+    AppState::GameOver => next_state.set(AppState::Playing),
+    AppState::Won => {
+      commands.remove_resource::<TargetReached>();
+      next_state.set(AppState::Playing);
     }
   }
 }
 
+/// Goes back to the game just won on a [`GameAction::KeepGoing`].
+fn apply_keep_going(
+  mut actions: EventReader<GameAction>,
+  mut next_state: ResMut<NextState<AppState>>,
+) {
+  if actions.read().any(|a| *a == GameAction::KeepGoing) {
+    next_state.set(AppState::Playing);
+  }
+}
+
+/// Turns a left mouse button drag or a touch swipe into a board shift.
+fn handle_swipes(
+  (mouse_input, touches): (Res<ButtonInput<MouseButton>>, Res<Touches>),
+  window: Single<&Window, With<PrimaryWindow>>,
+  settings: Res<GestureSettings>,
+  time: Res<Time<Real>>,
+  mut debounce: ResMut<InputDebounce>,
+  mut events: EventWriter<GameAction>,
+  mut swipe_start: Local<Option<(Vec2, Duration)>>,
+) {
+  let pressed = if mouse_input.just_pressed(MouseButton::Left) {
+    window.cursor_position()
+  } else {
+    touches.iter_just_pressed().next().map(|t| t.position())
+  };
+  if let Some(at) = pressed {
+    *swipe_start = Some((at, time.elapsed()));
+  }
+  let released = if mouse_input.just_released(MouseButton::Left) {
+    window.cursor_position()
+  } else {
+    touches.iter_just_released().next().map(|t| t.position())
+  };
+  if let Some(end) = released
+    && let Some((start, at)) = swipe_start.take()
+    && let Some(dir) =
+      classify_swipe(start, end, time.elapsed() - at, &settings)
+    && debounce.accept(dir, time.elapsed())
+  {
+    events.write(GameAction::Shift(dir));
+  }
+}
+
+/// Changes the [`BoardScale`] when scrolling over the grid with Ctrl held.
+fn scale_with_wheel(
+  scroll: Res<AccumulatedMouseScroll>,
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  grid: Single<&RelativeCursorPosition, With<Grid>>,
+  mut scale: ResMut<BoardScale>,
+) {
+  if scroll.delta.y == 0.0
+    || !grid.mouse_over()
+    || Modifiers::held(&keyboard_input) != Modifiers::CTRL
+  {
+    return;
+  }
+  let lines = match scroll.unit {
+    MouseScrollUnit::Line => scroll.delta.y,
+    // a line usually scrolls by a few dozen pixels
+    MouseScrollUnit::Pixel => scroll.delta.y / 40.0,
+  };
+  let scaled = BoardScale::new(scale.0 + lines * BoardScale::STEP);
+  scale.set_if_neq(scaled);
+}
+
 fn shift_board(
-  mut board_res: ResMut<BoardRes>,
-  mut board_events: EventReader<BoardShifted>,
+  state: Res<State<AppState>>,
+  (mut board_res, spawn_rng): (ResMut<BoardRes>, Option<ResMut<SpawnRng>>),
+  (mut stats, mut counters): (ResMut<DirectionStats>, ResMut<MoveCounters>),
+  (assist, adversary): (Option<Res<SpawnAssist>>, Option<Res<SpawnAdversary>>),
+  mut board_events: EventReader<GameAction>,
   mut tile_animated_events: EventWriter<TileAnimated>,
+  mut commands: Commands,
 ) {
-  let Some(event) = board_events.read().next() else {
+  // the run conditions keep this from happening, but a finished board must
+  // never change
+  debug_assert_eq!(
+    *state.get(),
+    AppState::Playing,
+    "board shifted outside of a game"
+  );
+  if *state.get() != AppState::Playing {
+    warn!("ignoring a shift in {:?}", state.get());
+    board_events.clear();
+    return;
+  }
+  let Some(dir) = board_events.read().find_map(GameAction::as_shift) else {
     return;
   };
-  let actions = board_res.0.shift(event.0);
+  let before = board_res.0.clone();
+  let actions = board_res.0.shift(dir);
   if actions.is_empty() {
     return;
   }
+  stats.record(dir, &actions);
+  counters.record(&actions);
+  commands.insert_resource(PendingMove {
+    direction: dir,
+    actions: actions.clone(),
+    before,
+  });
   tile_animated_events.write_batch(actions.into_iter().map(|a: TileAction| {
     match a.kind {
       TileActionKind::Move => TileAnimated::Moved {
-        value: a.value,
         from: a.from,
         to: a.to,
       },
       TileActionKind::Merge => TileAnimated::Merged {
-        value: a.value,
+        result: a.result_value,
         from: a.from,
         at: a.to,
       },
     }
   }));
-  if let Some((value, coords)) = board_res.0.spawn() {
-    tile_animated_events.write(TileAnimated::Spawned { value, at: coords });
-  }
+  let board = &mut board_res.0;
+  match (adversary, assist) {
+    (Some(adversary), _) => {
+      let depth = adversary.depth.clamp(1, SpawnAdversary::MAX_DEPTH);
+      let rate = |board: &Board<ROWS, COLS>| {
+        board.best_reply(depth, &adversary.evaluate)
+      };
+      board.spawn_evaluated(SpawnBias::Adversarial, rate)
+    }
+    (None, Some(assist)) if board.difficulty_estimate() >= assist.threshold => {
+      board.spawn_evaluated(SpawnBias::Assisted, assist.evaluate)
+    }
+    _ => match spawn_rng {
+      Some(mut rng) => board.spawn_with_rng(&mut rng.0),
+      None => board.spawn(),
+    },
+  };
 }
 
 fn assign_animations(
@@ -227,11 +931,14 @@ fn assign_animations(
   tiles: Single<&Children, With<Grid>>,
   mut commands: Commands,
 ) {
+  // tiles that have moved in this shift, by the cell they're moving to
+  let mut arrived = [[None; COLS]; ROWS];
   for e in tile_animated_events.read() {
     let (row, col): (usize, usize);
     let anim = match e {
       TileAnimated::Moved { from, to, .. } => {
         (row, col) = *from;
+        arrived[to.0][to.1] = Some(tiles[row * COLS + col]);
         let dir = direction_from_position(from, to);
         let tiles_to_move =
           from.0.abs_diff(to.0).max(from.1.abs_diff(to.1)) as f32;
@@ -241,25 +948,31 @@ fn assign_animations(
           tiles_to_move_left: tiles_to_move,
         }
       }
-      TileAnimated::Merged { value, from, at } => {
+      TileAnimated::Merged {
+        result, from, at, ..
+      } => {
         (row, col) = *from;
+        let into = arrived[at.0][at.1].unwrap_or(tiles[at.0 * COLS + at.1]);
         let dir = direction_from_position(from, at);
         let tiles_to_move =
           from.0.abs_diff(at.0).max(from.1.abs_diff(at.1)) as f32;
         Animation::Merge {
-          value: *value,
+          into,
+          value: *result,
           dir,
           tiles_to_move,
           tiles_to_move_left: tiles_to_move,
         }
       }
-      TileAnimated::Spawned { value, at } => {
-        (row, col) = *at;
-        return;
-      }
     };
-    let tile = tiles.get(row * SIZE + col).expect("tile out of bounds");
-    commands.entity(*tile).insert(anim);
+    // sliding tiles go above resting ones and merging tiles above the tiles
+    // they merge into
+    let z = match anim {
+      Animation::Move { .. } => ZIndex(1),
+      Animation::Merge { .. } => ZIndex(2),
+    };
+    let tile = tiles.get(row * COLS + col).expect("tile out of bounds");
+    commands.entity(*tile).insert((anim, z));
   }
 }
 
@@ -280,18 +993,158 @@ fn direction_from_position(
   }
 }
 
+/// Tile slide speed in tiles per second.
+pub(crate) const SLIDE_SPEED: f32 = 25.0;
+
+/// Returns the distances in logical pixels between origins of neighboring
+/// tiles in a row and in a column.
+fn tile_step(grid: &ComputedNode, tile: &ComputedNode) -> Vec2 {
+  let (padding, border) = (grid.padding(), grid.border());
+  let inner = grid.size()
+    - Vec2::new(
+      padding.left + padding.right + border.left + border.right,
+      padding.top + padding.bottom + border.top + border.bottom,
+    );
+  let lanes = Vec2::new(COLS as f32, ROWS as f32);
+  let step = (inner - tile.size()) / (lanes - 1.0).max(Vec2::ONE);
+  step * grid.inverse_scale_factor()
+}
+
+fn start_nudge(
+  grid_style: Res<GridStyle>,
+  mut board_events: EventReader<GameAction>,
+  grid: Single<Entity, With<Grid>>,
+  mut commands: Commands,
+) {
+  if let Some(dir) = board_events.read().find_map(GameAction::as_shift)
+    && grid_style.nudge
+  {
+    commands.entity(*grid).insert(Nudge::new(dir));
+  }
+}
+
+fn nudge_grid(
+  time: Res<Time>,
+  grid: Single<(Entity, &mut Nudge, &mut Node), With<Grid>>,
+  mut commands: Commands,
+) {
+  let (entity, mut nudge, mut node) = grid.into_inner();
+  nudge.elapsed += time.delta_secs();
+  let offset = Nudge::offset(nudge.elapsed);
+  (node.left, node.top) = match nudge.dir {
+    Direction::Up => (Val::Auto, Val::Px(-offset)),
+    Direction::Down => (Val::Auto, Val::Px(offset)),
+    Direction::Left => (Val::Px(-offset), Val::Auto),
+    Direction::Right => (Val::Px(offset), Val::Auto),
+  };
+  if nudge.is_finished() {
+    (node.left, node.top) = (Val::Auto, Val::Auto);
+    commands.entity(entity).remove::<Nudge>();
+  }
+}
+
 fn animate_tiles(
   time: Res<Time>,
-  animated_tiles: Query<(&mut Transform, &Animation, &Node), With<Tile>>,
+  grid: Single<&ComputedNode, With<Grid>>,
+  animated_tiles: Query<
+    (Entity, &mut Animation, &mut Node, &ComputedNode),
+    With<Tile>,
+  >,
+  mut commands: Commands,
 ) {
-  for (mut trans, anim, node) in animated_tiles {
-    trans.translation.x += time.delta_secs() * 1000.0;
-    println!("{}", trans.translation.x);
+  for (entity, mut anim, mut node, computed) in animated_tiles {
+    let (Animation::Move {
+      dir,
+      tiles_to_move,
+      tiles_to_move_left,
+    }
+    | Animation::Merge {
+      dir,
+      tiles_to_move,
+      tiles_to_move_left,
+      ..
+    }) = &mut *anim;
+    *tiles_to_move_left =
+      (*tiles_to_move_left - SLIDE_SPEED * time.delta_secs()).max(0.0);
+    let offset =
+      (*tiles_to_move - *tiles_to_move_left) * tile_step(&grid, computed);
+    match dir {
+      Direction::Up => node.top = Val::Px(-offset.y),
+      Direction::Down => node.top = Val::Px(offset.y),
+      Direction::Left => node.left = Val::Px(-offset.x),
+      Direction::Right => node.left = Val::Px(offset.x),
+    }
+    if *tiles_to_move_left > 0.0 {
+      continue;
+    }
+    let mut tile = commands.entity(entity);
+    tile.remove::<Animation>().insert(ZIndex::default());
+    if let Animation::Merge { into, value, .. } = *anim {
+      tile.insert(Visibility::Hidden);
+      commands.entity(into).insert(Pop::new(value));
+    }
   }
 }
 
-fn animating(animated_tiles: Query<(&Tile, &Animation)>) -> bool {
+fn pop_tiles(
+  time: Res<Time>,
+  popping_tiles: Query<
+    (
+      Entity,
+      &mut Pop,
+      &mut Transform,
+      &BackgroundColor,
+      &Children,
+    ),
+    With<Tile>,
+  >,
+  mut texts: Query<(&mut Text, &TextColor)>,
+  mut commands: Commands,
+) {
+  for (entity, mut pop, mut trans, background, children) in popping_tiles {
+    // colors fade over the whole pop while the number swaps at its peak
+    if pop.elapsed == 0.0 {
+      let to = style::tile_foreground(pop.value);
+      commands.entity(entity).insert(ColorTween::new(
+        background.0,
+        to,
+        Pop::DURATION,
+      ));
+      for child in children {
+        if let Ok((_, color)) = texts.get(*child) {
+          let to = style::tile_text(pop.value);
+          commands.entity(*child).insert(ColorTween::new(
+            color.0,
+            to,
+            Pop::DURATION,
+          ));
+        }
+      }
+    }
+    let (scale, flip) = pop.advance(time.delta_secs());
+    trans.scale = Vec3::new(scale, scale, 1.0);
+    if flip {
+      for child in children {
+        if let Ok((mut text, _)) = texts.get_mut(*child) {
+          text.0 = 2u32.pow(pop.value as u32).to_string();
+        }
+      }
+    }
+    if pop.is_finished() {
+      trans.scale = Vec3::ONE;
+      commands.entity(entity).remove::<Pop>();
+    }
+  }
+}
+
+fn animating(
+  animated_tiles: Query<(), (With<Tile>, With<Animation>)>,
+  popping_tiles: Query<(), (With<Tile>, With<Pop>)>,
+  fading_tiles: Query<(), (With<Tile>, With<ColorTween>)>,
+) -> bool {
   !animated_tiles.is_empty()
+    || !popping_tiles.is_empty()
+    || !fading_tiles.is_empty()
 }
 
 fn player_can_interact() -> impl Condition<()> {
@@ -300,16 +1153,969 @@ fn player_can_interact() -> impl Condition<()> {
 
 fn redraw_board(
   board: Res<BoardRes>,
-  grid: Single<Entity, With<Grid>>,
+  mut view: Single<&mut BoardView, With<Grid>>,
   mut commands: Commands,
 ) {
-  let tiles = board
-    .0
-    .iter_numbers()
-    .map(|n| commands.spawn(tile(n)).id())
-    .collect::<Vec<_>>();
-  commands
-    .entity(*grid)
-    .despawn_related::<Children>()
-    .replace_children(&tiles);
+  commands.remove_resource::<PendingMove>();
+  view.0.clone_from(&board.0);
+}
+
+/// Rebuilds the tiles of changed views. The live grid's tiles are named
+/// `tile-r{row}c{col}`, and those of other views get the view's [`Name`] as
+/// a prefix, or no name if the view has none, so that names stay unique.
+fn draw_board_views(
+  grid_style: Res<GridStyle>,
+  views: Query<(Entity, Ref<BoardView>, &mut BackgroundColor, Option<&Name>)>,
+  grid: Query<(), With<Grid>>,
+  mut commands: Commands,
+) {
+  for (entity, view, mut background, name) in views {
+    if !view.is_changed() && !grid_style.is_changed() {
+      continue;
+    }
+    background.0 = grid_style.gap_color.unwrap_or(style::GRID);
+    let prefix = match (grid.contains(entity), name) {
+      (true, _) => Some(String::new()),
+      (false, Some(name)) => Some(format!("{name}-")),
+      (false, None) => None,
+    };
+    let tiles = view
+      .0
+      .iter_cells()
+      .map(|((row, col), n)| {
+        let mut tile = commands.spawn(tile(n, grid_style.tile_shadow));
+        if let Some(prefix) = &prefix {
+          tile.insert(Name::new(format!("{prefix}tile-r{row}c{col}")));
+        }
+        tile.id()
+      })
+      .collect::<Vec<_>>();
+    commands
+      .entity(entity)
+      .despawn_related::<Children>()
+      .replace_children(&tiles);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::{
+    ecs::system::RunSystemOnce, sprite::BorderRect, state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+  };
+
+  use super::*;
+  use crate::find_by_name;
+
+  fn app(plugin: BoardPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin, plugin));
+    app.update();
+    app
+  }
+
+  fn set_board(app: &mut App, board: Board<ROWS, COLS>) {
+    app.world_mut().resource_mut::<BoardRes>().0 = board;
+  }
+
+  fn state(app: &App) -> AppState {
+    *app.world().resource::<State<AppState>>().get()
+  }
+
+  fn overlay_shown(app: &mut App) -> bool {
+    find_by_name(app.world_mut(), "overlay-gameover").is_some()
+  }
+
+  #[test]
+  fn debounce() {
+    use Direction::*;
+
+    let ms = Duration::from_millis;
+    let mut debounce = InputDebounce::new(ms(80));
+    assert!(debounce.accept(Left, ms(1000)));
+    assert!(!debounce.accept(Left, ms(1010)));
+    assert!(!debounce.accept(Left, ms(1079)));
+    assert!(debounce.accept(Left, ms(1080)));
+    assert!(debounce.accept(Right, ms(1090)));
+    assert!(debounce.accept(Left, ms(1100)));
+    assert!(debounce.accept(Left, ms(1500)));
+  }
+
+  #[test]
+  fn pop_flips_number_at_peak() {
+    let dt = Pop::DURATION / 10.0;
+    let mut pop = Pop::new(3);
+    let mut flips = Vec::new();
+    let mut peak = (0, 0.0);
+    for step in 1..=10 {
+      let (scale, flip) = pop.advance(dt);
+      if flip {
+        flips.push(step);
+      }
+      if scale > peak.1 {
+        peak = (step, scale);
+      }
+      assert!(pop.is_finished() == (step == 10), "step {step}");
+    }
+    assert_eq!(flips, vec![5], "number should be swapped once, at the peak");
+    assert_eq!(peak, (5, Pop::SCALE));
+
+    let mut pop = Pop::new(3);
+    let (scale, flip) = pop.advance(Pop::DURATION * 2.0);
+    assert!(flip, "a long frame must not skip the swap");
+    assert!((scale - 1.0).abs() < 1e-6);
+    assert!(pop.is_finished());
+  }
+
+  #[test]
+  fn animated_tiles_go_on_top() {
+    let mut app = app(BoardPlugin::default().without_input());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+      Duration::from_millis(10),
+    ));
+    set_board(
+      &mut app,
+      Board([
+        [1, 0, 0, 1], //
+        [0, 0, 1, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+      ]),
+    );
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    app.update();
+    let z_indices = |app: &mut App| {
+      let tiles = app
+        .world_mut()
+        .query_filtered::<&Children, With<Grid>>()
+        .single(app.world())
+        .unwrap()
+        .to_vec();
+      tiles
+        .into_iter()
+        .map(|t| app.world().get::<ZIndex>(t).map_or(0, |z| z.0))
+        .collect::<Vec<_>>()
+    };
+    let mut expected = vec![0; ROWS * COLS];
+    expected[3] = 2;
+    expected[6] = 1;
+    assert_eq!(z_indices(&mut app), expected);
+    for _ in 0..100 {
+      app.update();
+    }
+    assert_eq!(
+      app
+        .world_mut()
+        .query_filtered::<(), With<Animation>>()
+        .iter(app.world())
+        .count(),
+      0
+    );
+    assert_eq!(z_indices(&mut app), vec![0; ROWS * COLS]);
+  }
+
+  #[test]
+  fn nudge_and_return() {
+    assert_eq!(Nudge::offset(0.0), 0.0);
+    assert_eq!(Nudge::offset(Nudge::DURATION / 2.0), Nudge::DISTANCE);
+    assert!(Nudge::offset(Nudge::DURATION / 4.0) > Nudge::DISTANCE / 2.0);
+    assert!(Nudge::offset(Nudge::DURATION) < 1e-5);
+    assert_eq!(
+      Nudge::offset(Nudge::DURATION * 2.0),
+      Nudge::offset(Nudge::DURATION)
+    );
+
+    let mut app = app(BoardPlugin::default().without_input());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+      Duration::from_millis(10),
+    ));
+    app.world_mut().resource_mut::<GridStyle>().nudge = true;
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Right));
+    app.update();
+    let grid_left = |app: &mut App| {
+      app
+        .world_mut()
+        .query_filtered::<&Node, With<Grid>>()
+        .single(app.world())
+        .unwrap()
+        .left
+    };
+    let mut peak = 0.0f32;
+    for _ in 0..20 {
+      app.update();
+      if let Val::Px(px) = grid_left(&mut app) {
+        peak = peak.max(px);
+      }
+    }
+    assert!(peak > Nudge::DISTANCE / 2.0);
+    assert_eq!(grid_left(&mut app), Val::Auto);
+  }
+
+  #[test]
+  fn rescale_mid_move() {
+    assert_eq!(BoardScale::new(0.2), BoardScale::new(BoardScale::MIN));
+    assert_eq!(BoardScale::new(1.5), BoardScale::default());
+
+    /// Lays the grid out as a 400 px square times the scale, as the UI would
+    /// in a window, which tests don't have.
+    fn layout(
+      scale: Res<BoardScale>,
+      mut grid: Single<&mut ComputedNode, With<Grid>>,
+      tiles: Query<&mut ComputedNode, (With<Tile>, Without<Grid>)>,
+    ) {
+      let (size, frame) = (400.0 * scale.get(), 12.0 * scale.get());
+      grid.size = Vec2::splat(size);
+      grid.border = BorderRect::all(frame);
+      // gaps are as wide as the frame
+      let tile = (size - 2.0 * frame - 3.0 * frame) / 4.0;
+      for mut computed in tiles {
+        computed.size = Vec2::splat(tile);
+      }
+    }
+
+    #[derive(Resource, Default)]
+    struct Slid(f32);
+
+    fn track(tiles: Query<&Node, With<Tile>>, mut slid: ResMut<Slid>) {
+      for node in tiles {
+        if let Val::Px(px) = node.left {
+          slid.0 = px;
+        }
+      }
+    }
+
+    let mut app = app(BoardPlugin::default().without_input());
+    app
+      .insert_resource(TimeUpdateStrategy::ManualDuration(
+        Duration::from_millis(10),
+      ))
+      .init_resource::<Slid>()
+      .add_systems(
+        Update,
+        (
+          layout.before(BoardSet::Animate),
+          track.after(BoardSet::Animate).before(BoardSet::Commit),
+        ),
+      );
+    set_board(
+      &mut app,
+      Board([
+        [1, 0, 0, 0], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+      ]),
+    );
+    app.update();
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Right));
+    for _ in 0..6 {
+      app.update();
+    }
+    // 3 slots of 85 + 12 px on a full scale board
+    let halfway = app.world().resource::<Slid>().0;
+    assert!(halfway > 0.0 && halfway < 291.0, "{halfway}");
+
+    app.insert_resource(BoardScale::new(0.5));
+    app.update();
+    let grid = app
+      .world_mut()
+      .query_filtered::<&Node, With<Grid>>()
+      .single(app.world())
+      .unwrap();
+    assert_eq!(grid.max_width, Val::VMin(50.0));
+    assert!(app.world().resource::<Slid>().0 < 150.0);
+    for _ in 0..60 {
+      app.update();
+    }
+    // 3 slots of 42.5 + 6 px on a half scale board
+    let landed = app.world().resource::<Slid>().0;
+    assert!((landed - 145.5).abs() < 1e-3, "{landed}");
+  }
+
+  #[test]
+  fn snapshot_lags_until_commit() {
+    let mut app = app(BoardPlugin::default().without_input());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+      Duration::from_millis(10),
+    ));
+    let before = Board([
+      [1, 0, 0, 1], //
+      [0, 0, 0, 2],
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+    ]);
+    set_board(&mut app, before.clone());
+    app.world_mut().resource_mut::<GridStyle>().set_changed();
+    app.update();
+
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    let snapshot = |app: &mut App| {
+      app
+        .world_mut()
+        .run_system_once(|s: BoardSnapshot| {
+          (s.board().clone(), s.pending().is_some())
+        })
+        .unwrap()
+    };
+    let drawn = |app: &mut App| {
+      let tiles = app
+        .world_mut()
+        .query_filtered::<&Children, With<Grid>>()
+        .single(app.world())
+        .unwrap()
+        .to_vec();
+      tiles
+        .into_iter()
+        .map(|t| app.world().get::<BackgroundColor>(t).unwrap().0)
+        .collect::<Vec<_>>()
+    };
+    let mut commits = 0;
+    let mut was_pending = false;
+    for _ in 0..60 {
+      app.update();
+      let (board, pending) = snapshot(&mut app);
+      if pending {
+        assert_eq!(board, before);
+      } else {
+        assert_eq!(board, app.world().resource::<BoardRes>().0);
+        let colors = board
+          .iter_numbers()
+          .map(style::tile_foreground)
+          .collect::<Vec<_>>();
+        assert_eq!(drawn(&mut app), colors);
+      }
+      commits += (was_pending && !pending) as u32;
+      was_pending = pending;
+    }
+    assert_eq!(commits, 1);
+    assert_ne!(app.world().resource::<BoardRes>().0, before);
+  }
+
+  #[test]
+  fn redraw_outlives_shift_event() {
+    let mut app = app(BoardPlugin::default().without_input());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+      Duration::from_millis(5),
+    ));
+    set_board(
+      &mut app,
+      Board([
+        [0, 0, 0, 1], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+      ]),
+    );
+    app.world_mut().resource_mut::<GridStyle>().set_changed();
+    app.update();
+    let tiles = |app: &mut App| {
+      app
+        .world_mut()
+        .query_filtered::<&Children, With<Grid>>()
+        .single(app.world())
+        .unwrap()
+        .to_vec()
+    };
+    let mut drawn = tiles(&mut app);
+
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    let mut redraws = vec![];
+    for frame in 0..120 {
+      app.update();
+      let now = tiles(&mut app);
+      if now != drawn {
+        assert!(!app.world_mut().run_system_once(animating).unwrap());
+        redraws.push(frame);
+        drawn = now;
+      }
+    }
+    // the slide takes several frames, so the event is long gone by the time
+    // the board is redrawn
+    assert_eq!(redraws.len(), 1, "{redraws:?}");
+    assert!(redraws[0] > 2, "{redraws:?}");
+    assert!(!app.world().contains_resource::<PendingMove>());
+    assert_eq!(app.world().resource::<BoardRes>().0.get(0, 0), 1);
+  }
+
+  #[test]
+  fn reflection_round_trip() {
+    use bevy::reflect::{
+      FromReflect, PartialReflect,
+      serde::{ReflectDeserializer, ReflectSerializer},
+    };
+    use serde::de::DeserializeSeed;
+
+    let app = app(BoardPlugin::default().without_input());
+    let registry = app.world().resource::<AppTypeRegistry>().read();
+    let round_trip = |value: &dyn PartialReflect| {
+      let ron = ron::to_string(&ReflectSerializer::new(value, &registry))
+        .expect("serializable");
+      let mut deserializer = ron::Deserializer::from_str(&ron).unwrap();
+      let back = ReflectDeserializer::new(&registry)
+        .deserialize(&mut deserializer)
+        .expect("deserializable");
+      assert_eq!(value.reflect_partial_eq(&*back), Some(true), "{ron}");
+      back
+    };
+    let board = BoardRes(Board::fill_distinct(3));
+    let back = round_trip(&board);
+    assert_eq!(BoardRes::from_reflect(&*back).unwrap().0, board.0);
+    round_trip(&GridStyle {
+      tile_shadow: true,
+      gap_color: Some(style::GRID),
+      nudge: true,
+      merge_badges: true,
+    });
+    round_trip(&StartingLayout(InitialLayout::Fixed(vec![((1, 2), 3)])));
+    round_trip(&DirectionStats::default());
+    round_trip(&Grid);
+    round_trip(&Tile);
+    round_trip(&Animation::Merge {
+      into: Entity::from_raw(7),
+      value: 3,
+      dir: Direction::Down,
+      tiles_to_move: 2.0,
+      tiles_to_move_left: 0.5,
+    });
+  }
+
+  #[test]
+  fn gap_color() {
+    let mut app = app(BoardPlugin::default().without_input());
+    let colors = |app: &mut App| {
+      app
+        .world_mut()
+        .query_filtered::<(&BackgroundColor, &BorderColor), With<Grid>>()
+        .single(app.world())
+        .map(|(background, border)| (background.0, border.0))
+        .unwrap()
+    };
+    assert_eq!(colors(&mut app), (style::GRID, style::GRID));
+    let gap = Color::srgb_u8(0x77, 0x6E, 0x65);
+    app.world_mut().resource_mut::<GridStyle>().gap_color = Some(gap);
+    app.update();
+    assert_eq!(colors(&mut app), (gap, style::GRID));
+  }
+
+  #[test]
+  fn board_views() {
+    let mut app = app(BoardPlugin::default().without_input());
+    let boards = [
+      Board::empty(),
+      Board::from_code("1200000000000003").unwrap(),
+      Board::fill_distinct(1),
+    ];
+    let views = boards
+      .iter()
+      .map(|board| app.world_mut().spawn(BoardView(board.clone())).id())
+      .collect::<Vec<_>>();
+    app.update();
+    let drawn = |app: &App, view| {
+      app
+        .world()
+        .get::<Children>(view)
+        .unwrap()
+        .iter()
+        .map(|tile| {
+          let texts = app.world().get::<Children>(tile);
+          (
+            app.world().get::<BackgroundColor>(tile).unwrap().0,
+            texts.map_or(0, |t| t.len()),
+          )
+        })
+        .collect::<Vec<_>>()
+    };
+    let expected = |board: &Board<ROWS, COLS>| {
+      board
+        .iter_numbers()
+        .map(|n| (style::tile_foreground(n), (n > 0) as usize))
+        .collect::<Vec<_>>()
+    };
+    for (view, board) in views.iter().zip(&boards) {
+      assert_eq!(drawn(&app, *view), expected(board));
+    }
+
+    // views are redrawn independently of each other and of the live game
+    let live = app.world().resource::<BoardRes>().0.clone();
+    let before = views
+      .iter()
+      .map(|v| app.world().get::<Children>(*v).unwrap().to_vec())
+      .collect::<Vec<_>>();
+    app.world_mut().get_mut::<BoardView>(views[0]).unwrap().0 =
+      Board::fill_distinct(5);
+    app.update();
+    assert_eq!(drawn(&app, views[0]), expected(&Board::fill_distinct(5)));
+    for i in 1..3 {
+      assert_eq!(
+        app.world().get::<Children>(views[i]).unwrap().to_vec(),
+        before[i]
+      );
+    }
+    assert_eq!(app.world().resource::<BoardRes>().0, live);
+  }
+
+  #[test]
+  fn tile_names_with_views() {
+    let mut app = app(BoardPlugin::default().without_input());
+    let preview = app
+      .world_mut()
+      .spawn((Name::new("preview"), BoardView(Board::fill_distinct(1))))
+      .id();
+    let unnamed = app.world_mut().spawn(BoardView(Board::empty())).id();
+    app.update();
+    let parent = |app: &mut App, name| {
+      let tile = find_by_name(app.world_mut(), name).unwrap();
+      app.world().get::<ChildOf>(tile).unwrap().parent()
+    };
+    let grid = find_by_name(app.world_mut(), "grid").unwrap();
+    assert_eq!(parent(&mut app, "tile-r2c3"), grid);
+    assert_eq!(parent(&mut app, "preview-tile-r2c3"), preview);
+    let named_tiles = app
+      .world_mut()
+      .query::<(&Name, &ChildOf)>()
+      .iter(app.world())
+      .filter(|(name, _)| name.contains("tile-"))
+      .map(|(_, child_of)| child_of.parent())
+      .collect::<Vec<_>>();
+    assert_eq!(named_tiles.len(), 2 * ROWS * COLS);
+    assert!(!named_tiles.contains(&unnamed));
+    assert_eq!(
+      app.world().get::<Children>(unnamed).unwrap().len(),
+      ROWS * COLS
+    );
+  }
+
+  #[test]
+  fn named_entities() {
+    let mut app = app(BoardPlugin::default().without_input());
+    let mut names = app
+      .world_mut()
+      .query::<&Name>()
+      .iter(app.world())
+      .map(|n| n.to_string())
+      .collect::<Vec<_>>();
+    names.sort();
+    let mut golden = vec!["camera".to_string(), "grid".to_string()];
+    for row in 0..ROWS {
+      for col in 0..COLS {
+        golden.push(format!("tile-r{row}c{col}"));
+      }
+    }
+    golden.sort();
+    assert_eq!(names, golden);
+
+    let tile = find_by_name(app.world_mut(), "tile-r2c3").unwrap();
+    let grid = find_by_name(app.world_mut(), "grid").unwrap();
+    let children = app.world().get::<Children>(grid).unwrap();
+    assert_eq!(children[2 * COLS + 3], tile);
+    assert_eq!(find_by_name(app.world_mut(), "tile-r4c0"), None);
+  }
+
+  #[test]
+  fn entities_stay_bounded() {
+    let mut app =
+      app(BoardPlugin::default().without_input().without_overlays());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+      Duration::from_millis(20),
+    ));
+    // the camera, the grid and a tile with a number on every cell
+    let bound = 2 + 2 * ROWS * COLS;
+    let mut most = 0;
+    for step in 0..300 {
+      let dir = Direction::ALL[step % 4];
+      app.world_mut().send_event(GameAction::Shift(dir));
+      for _ in 0..20 {
+        app.update();
+      }
+      if state(&app) == AppState::GameOver {
+        app.world_mut().send_event(GameAction::Restart);
+        app.update();
+        app.update();
+      }
+      most = most.max(app.world().entities().len() as usize);
+    }
+    assert!(most <= bound, "{most} entities, expected at most {bound}");
+  }
+
+  #[test]
+  fn shift_without_input() {
+    let mut app = app(BoardPlugin::default().without_input());
+    set_board(
+      &mut app,
+      Board([
+        [0, 0, 0, 1], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+      ]),
+    );
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    app.update();
+    let board = &app.world().resource::<BoardRes>().0;
+    assert_eq!(board.get(0, 0), 1);
+    assert_eq!(board.iter_numbers().filter(|n| *n != 0).count(), 2);
+  }
+
+  #[test]
+  fn start_from_initial_board() {
+    let board = Board::from_code("1200000000000003").unwrap();
+    let mut app = App::new();
+    app
+      .add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        BoardPlugin::default().without_input(),
+      ))
+      .insert_resource(InitialBoard(board.clone()));
+    app.update();
+    assert_eq!(app.world().resource::<BoardRes>().0, board);
+    assert!(!app.world().contains_resource::<InitialBoard>());
+  }
+
+  #[test]
+  fn seeded_games_repeat() {
+    let play = || {
+      let mut app = App::new();
+      app
+        .add_plugins((
+          MinimalPlugins,
+          StatesPlugin,
+          BoardPlugin::default().without_input(),
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+          Duration::from_millis(20),
+        ))
+        .insert_resource(SpawnRng(StdRng::seed_from_u64(7)));
+      app.update();
+      let mut boards = vec![app.world().resource::<BoardRes>().0.clone()];
+      for dir in Direction::ALL.into_iter().cycle().take(12) {
+        app.world_mut().send_event(GameAction::Shift(dir));
+        for _ in 0..20 {
+          app.update();
+        }
+        boards.push(app.world().resource::<BoardRes>().0.clone());
+      }
+      boards
+    };
+    let boards = play();
+    assert_eq!(boards[0], Board::new_seeded(7));
+    assert!(boards.windows(2).any(|pair| pair[0] != pair[1]));
+    assert_eq!(boards, play());
+  }
+
+  #[test]
+  fn game_over_with_overlays() {
+    let mut app = app(BoardPlugin::default().without_input());
+    set_board(&mut app, Board::fill_distinct(1));
+    app.update();
+    app.update();
+    assert_eq!(state(&app), AppState::GameOver);
+    assert!(overlay_shown(&mut app));
+    app
+      .world_mut()
+      .resource_mut::<NextState<AppState>>()
+      .set(AppState::Playing);
+    app.update();
+    assert_eq!(state(&app), AppState::Playing);
+    assert!(!overlay_shown(&mut app));
+  }
+
+  #[test]
+  fn overlays_cover_grid() {
+    let mut app = app(BoardPlugin::default().without_input());
+    set_board(&mut app, Board::fill_distinct(1));
+    app.update();
+    app.update();
+    let sizes = |app: &mut App| {
+      let mut nodes = app
+        .world_mut()
+        .query_filtered::<&Node, Or<(With<Grid>, With<CoversGrid>)>>();
+      nodes
+        .iter(app.world())
+        .map(|node| (node.max_width, node.aspect_ratio))
+        .collect::<Vec<_>>()
+    };
+    for scale in [1.0, 0.5] {
+      app.insert_resource(BoardScale::new(scale));
+      app.update();
+      let sizes = sizes(&mut app);
+      assert_eq!(sizes.len(), 2);
+      assert!(sizes.iter().all(|size| *size == sizes[0]), "{sizes:?}");
+      assert_eq!(sizes[0].1, Some(COLS as f32 / ROWS as f32));
+    }
+  }
+
+  #[test]
+  fn win_and_keep_going() {
+    let mut app = app(BoardPlugin::default().without_input());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+      Duration::from_millis(10),
+    ));
+    let win_shown =
+      |app: &mut App| find_by_name(app.world_mut(), "overlay-win");
+    set_board(
+      &mut app,
+      Board([
+        [10, 10, 0, 0], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+      ]),
+    );
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    for _ in 0..60 {
+      app.update();
+    }
+    assert_eq!(state(&app), AppState::Won);
+    assert!(win_shown(&mut app).is_some());
+    let won = app.world().resource::<BoardRes>().0.clone();
+    assert_eq!(won.get(0, 0), 11);
+
+    app.world_mut().send_event(GameAction::KeepGoing);
+    for _ in 0..5 {
+      app.update();
+    }
+    // the same game goes on without winning it again
+    assert_eq!(state(&app), AppState::Playing);
+    assert!(win_shown(&mut app).is_none());
+    assert_eq!(app.world().resource::<BoardRes>().0, won);
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Right));
+    for _ in 0..60 {
+      app.update();
+    }
+    assert_eq!(state(&app), AppState::Playing);
+    let board = &app.world().resource::<BoardRes>().0;
+    assert_ne!(*board, won);
+    assert!(board.has_reached(11));
+
+    // a new game can be won again
+    app.world_mut().send_event(GameAction::Restart);
+    app.update();
+    assert!(!app.world().resource::<BoardRes>().0.has_reached(11));
+    app.world_mut().resource_mut::<WinTarget>().0 = 2;
+    set_board(
+      &mut app,
+      Board([
+        [1, 1, 0, 0], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+      ]),
+    );
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    for _ in 0..60 {
+      app.update();
+    }
+    assert_eq!(state(&app), AppState::Won);
+
+    // starting over from the win resets the board
+    app.world_mut().resource_mut::<WinTarget>().0 = 11;
+    app.world_mut().send_event(GameAction::Restart);
+    app.update();
+    app.update();
+    assert_eq!(state(&app), AppState::Playing);
+    assert!(win_shown(&mut app).is_none());
+    let board = &app.world().resource::<BoardRes>().0;
+    assert_eq!(board.iter_numbers().filter(|n| *n != 0).count(), 2);
+  }
+
+  #[test]
+  fn no_shift_after_game_over() {
+    let mut app = app(BoardPlugin::default().without_input());
+    let board = Board([
+      [0, 0, 0, 1], //
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+    ]);
+    set_board(&mut app, board.clone());
+    app
+      .world_mut()
+      .resource_mut::<NextState<AppState>>()
+      .set(AppState::GameOver);
+    app.update();
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    app.update();
+    app.update();
+    assert_eq!(state(&app), AppState::GameOver);
+    assert_eq!(app.world().resource::<BoardRes>().0, board);
+  }
+
+  #[test]
+  fn restart_action() {
+    let mut app = app(BoardPlugin::default().without_input());
+    let board = Board::fill_distinct(1);
+    set_board(&mut app, board.clone());
+    app.update();
+    app.update();
+    assert_eq!(state(&app), AppState::GameOver);
+
+    app.world_mut().send_event(GameAction::Restart);
+    app.update();
+    app.update();
+    assert_eq!(state(&app), AppState::Playing);
+    assert_ne!(app.world().resource::<BoardRes>().0, board);
+
+    let full = Board([
+      [1, 2, 3, 4], //
+      [5, 6, 7, 8],
+      [1, 2, 3, 4],
+      [5, 6, 7, 0],
+    ]);
+    set_board(&mut app, full.clone());
+    app.world_mut().send_event(GameAction::Restart);
+    app.update();
+    assert_eq!(state(&app), AppState::Playing);
+    let board = &app.world().resource::<BoardRes>().0;
+    assert_ne!(*board, full);
+    assert_eq!(board.iter_numbers().filter(|n| *n != 0).count(), 2);
+  }
+
+  #[test]
+  fn game_over_on_move_limit() {
+    let mut app = app(BoardPlugin::default().without_input());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(
+      Duration::from_millis(10),
+    ));
+    app.world_mut().resource_mut::<GameOverRules>().max_moves = Some(1);
+    set_board(
+      &mut app,
+      Board([
+        [0, 0, 0, 1], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+      ]),
+    );
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    for _ in 0..60 {
+      app.update();
+    }
+    assert_eq!(state(&app), AppState::GameOver);
+    assert_eq!(
+      app.world().get_resource::<GameOverReason>(),
+      Some(&GameOverReason::MoveLimit(1))
+    );
+    let reason = find_by_name(app.world_mut(), "gameover-reason").unwrap();
+    assert_eq!(app.world().get::<Text>(reason).unwrap().0, "out of moves");
+  }
+
+  #[test]
+  fn assisted_spawns() {
+    let mut app = app(BoardPlugin::default().without_input());
+    app
+      .insert_resource(TimeUpdateStrategy::ManualDuration(
+        Duration::from_millis(10),
+      ))
+      .insert_resource(SpawnAssist {
+        threshold: 0.0,
+        evaluate: |board| board.get(3, 3) as f32,
+      });
+    app.world_mut().resource_mut::<GameOverRules>().max_moves = Some(1);
+    set_board(
+      &mut app,
+      Board([
+        [0, 0, 0, 1], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+      ]),
+    );
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    for _ in 0..60 {
+      app.update();
+    }
+    assert_eq!(
+      app.world().resource::<BoardRes>().0,
+      Board([
+        [1, 0, 0, 0], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [0, 0, 0, 2],
+      ])
+    );
+    let reason = find_by_name(app.world_mut(), "gameover-reason").unwrap();
+    assert_eq!(
+      app.world().get::<Text>(reason).unwrap().0,
+      "out of moves (assisted)"
+    );
+  }
+
+  #[test]
+  fn adversarial_spawns() {
+    let mut app = app(BoardPlugin::default().without_input());
+    let adversary = SpawnAdversary {
+      depth: 1,
+      evaluate: |board| board.occupancy().count_empty() as f32,
+    };
+    app
+      .insert_resource(TimeUpdateStrategy::ManualDuration(
+        Duration::from_millis(10),
+      ))
+      .insert_resource(adversary)
+      .insert_resource(SpawnAssist {
+        threshold: 0.0,
+        ..default()
+      });
+    app.world_mut().resource_mut::<GameOverRules>().max_moves = Some(1);
+    let board = Board([
+      [1, 1, 2, 0], //
+      [3, 0, 2, 0],
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+    ]);
+    set_board(&mut app, board.clone());
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    for _ in 0..60 {
+      app.update();
+    }
+    let mut expected = board;
+    expected.shift(Direction::Left);
+    expected.spawn_evaluated(SpawnBias::Adversarial, |board| {
+      board.best_reply(1, &adversary.evaluate)
+    });
+    assert_eq!(app.world().resource::<BoardRes>().0, expected);
+    let reason = find_by_name(app.world_mut(), "gameover-reason").unwrap();
+    assert_eq!(
+      app.world().get::<Text>(reason).unwrap().0,
+      "out of moves (adversarial)"
+    );
+  }
+
+  #[test]
+  fn game_over_without_overlays() {
+    let mut app =
+      app(BoardPlugin::default().without_input().without_overlays());
+    set_board(&mut app, Board::fill_distinct(1));
+    app.update();
+    app.update();
+    assert_eq!(state(&app), AppState::GameOver);
+    assert!(!overlay_shown(&mut app));
+  }
 }
@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeSystem};
+
+use crate::board::{Animation, Pop};
+
+/// Developer tools for tuning animations. Only compiled into debug builds with
+/// the `debug-ui` feature enabled.
+pub(crate) struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<TimeControl>()
+      .add_systems(Startup, spawn_debug_overlay)
+      .add_systems(First, control_time.after(TimeSystem))
+      .add_systems(Last, update_debug_overlay);
+  }
+}
+
+/// How fast virtual time runs relative to real time.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub(crate) enum TimeMode {
+  #[default]
+  Normal,
+  SlowMotion,
+  /// Virtual time stands still and only advances by [`TimeControl::STEP`]
+  /// on request.
+  Frozen,
+}
+
+/// Drives [`Time<Virtual>`]: F6 toggles slow motion, F7 freezes the game
+/// and then steps it by one tick per press.
+#[derive(Resource, Default, Debug)]
+pub(crate) struct TimeControl {
+  pub(crate) mode: TimeMode,
+}
+
+impl TimeControl {
+  const SLOW_MOTION_SPEED: f32 = 0.25;
+  /// Virtual time a single step advances by.
+  pub(crate) const STEP: Duration = Duration::from_micros(16_667);
+}
+
+/// Runs right after the clocks update, so a step is seen by this frame's
+/// systems. Keys pressed in the previous frame are still marked as just
+/// pressed here.
+fn control_time(
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  mut control: ResMut<TimeControl>,
+  mut virtual_time: ResMut<Time<Virtual>>,
+  mut time: ResMut<Time>,
+) {
+  let mut step = false;
+  if keyboard_input.just_pressed(KeyCode::F6) {
+    control.mode = match control.mode {
+      TimeMode::Normal => TimeMode::SlowMotion,
+      TimeMode::SlowMotion | TimeMode::Frozen => TimeMode::Normal,
+    };
+  }
+  if keyboard_input.just_pressed(KeyCode::F7) {
+    step = control.mode == TimeMode::Frozen;
+    control.mode = TimeMode::Frozen;
+  }
+  match control.mode {
+    TimeMode::Normal => {
+      virtual_time.set_relative_speed(1.0);
+      virtual_time.unpause();
+    }
+    TimeMode::SlowMotion => {
+      virtual_time.set_relative_speed(TimeControl::SLOW_MOTION_SPEED);
+      virtual_time.unpause();
+    }
+    TimeMode::Frozen => virtual_time.pause(),
+  }
+  if step {
+    virtual_time.advance_by(TimeControl::STEP);
+    *time = virtual_time.as_generic();
+  }
+}
+
+#[derive(Component)]
+struct DebugOverlay;
+
+fn spawn_debug_overlay(mut commands: Commands) {
+  commands.spawn((
+    DebugOverlay,
+    Name::new("overlay-debug"),
+    Node {
+      position_type: PositionType::Absolute,
+      top: Val::Px(4.0),
+      left: Val::Px(4.0),
+      ..default()
+    },
+    GlobalZIndex(i32::MAX),
+    Text::default(),
+    TextFont::from_font_size(14.0),
+    TextColor(Color::WHITE),
+    BackgroundColor(Color::BLACK.with_alpha(0.5)),
+  ));
+}
+
+fn update_debug_overlay(
+  control: Res<TimeControl>,
+  animations: Query<(Entity, &Animation)>,
+  pops: Query<(Entity, &Pop)>,
+  mut overlay: Single<&mut Text, With<DebugOverlay>>,
+) {
+  let mut lines = vec![match control.mode {
+    TimeMode::Normal => "time: 1x".to_string(),
+    TimeMode::SlowMotion => {
+      format!("time: {}x", TimeControl::SLOW_MOTION_SPEED)
+    }
+    TimeMode::Frozen => "time: frozen (F7 to step)".to_string(),
+  }];
+  lines.extend(animations.iter().map(|(e, anim)| format!("{e}: {anim}")));
+  lines.extend(pops.iter().map(|(e, pop)| format!("{e}: {pop}")));
+  overlay.0 = lines.join("\n");
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+  use super::*;
+  use crate::{
+    BoardPlugin, Direction, GameAction,
+    board::{BoardRes, SLIDE_SPEED},
+    domain::Board,
+  };
+
+  fn app() -> App {
+    let mut app = App::new();
+    app
+      .add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        BoardPlugin::default().without_input(),
+        DebugPlugin,
+      ))
+      .init_resource::<ButtonInput<KeyCode>>()
+      .insert_resource(TimeUpdateStrategy::ManualDuration(
+        Duration::from_millis(10),
+      ));
+    app.update();
+    app
+  }
+
+  fn press(app: &mut App, key: KeyCode) {
+    app
+      .world_mut()
+      .resource_mut::<ButtonInput<KeyCode>>()
+      .press(key);
+    app.update();
+    app
+      .world_mut()
+      .resource_mut::<ButtonInput<KeyCode>>()
+      .clear();
+    app
+      .world_mut()
+      .resource_mut::<ButtonInput<KeyCode>>()
+      .release(key);
+  }
+
+  fn tiles_left(app: &mut App) -> Vec<f32> {
+    app
+      .world_mut()
+      .query::<&Animation>()
+      .iter(app.world())
+      .map(|anim| match anim {
+        Animation::Move {
+          tiles_to_move_left, ..
+        }
+        | Animation::Merge {
+          tiles_to_move_left, ..
+        } => *tiles_to_move_left,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn step_advances_by_fixed_delta() {
+    let mut app = app();
+    press(&mut app, KeyCode::F7);
+    assert_eq!(app.world().resource::<TimeControl>().mode, TimeMode::Frozen);
+
+    app.world_mut().resource_mut::<BoardRes>().0 = Board([
+      [0, 0, 0, 1], //
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+    ]);
+    app
+      .world_mut()
+      .send_event(GameAction::Shift(Direction::Left));
+    app.update();
+    assert_eq!(tiles_left(&mut app), vec![3.0]);
+    app.update();
+    assert_eq!(tiles_left(&mut app), vec![3.0]);
+
+    press(&mut app, KeyCode::F7);
+    let expected = 3.0 - SLIDE_SPEED * TimeControl::STEP.as_secs_f32();
+    assert_eq!(tiles_left(&mut app), vec![expected]);
+    app.update();
+    assert_eq!(tiles_left(&mut app), vec![expected]);
+  }
+
+  #[test]
+  fn input_works_while_frozen() {
+    let mut app = app();
+    press(&mut app, KeyCode::F7);
+    app.update();
+    assert!(app.world().resource::<Time<Virtual>>().is_paused());
+    assert!(!app.world().resource::<Time<Real>>().delta().is_zero());
+
+    press(&mut app, KeyCode::F6);
+    assert_eq!(app.world().resource::<TimeControl>().mode, TimeMode::Normal);
+    assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+
+    press(&mut app, KeyCode::F6);
+    assert_eq!(
+      app.world().resource::<Time<Virtual>>().relative_speed(),
+      TimeControl::SLOW_MOTION_SPEED
+    );
+  }
+}
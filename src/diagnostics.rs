@@ -0,0 +1,126 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use bevy::{
+  diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+  prelude::*,
+};
+
+use crate::board::{Animation, BoardRes, Pop};
+
+/// Shows FPS, frame time, the number of running animations and a hash of the
+/// board in the corner of the screen. Toggled with F3, hidden by default.
+pub(crate) struct DiagnosticsOverlayPlugin;
+
+impl Plugin for DiagnosticsOverlayPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_plugins(FrameTimeDiagnosticsPlugin::default())
+      .add_systems(Startup, spawn_diagnostics_overlay)
+      .add_systems(
+        Update,
+        (toggle_diagnostics_overlay, update_diagnostics_overlay).chain(),
+      );
+  }
+}
+
+#[derive(Component)]
+struct DiagnosticsOverlay;
+
+fn spawn_diagnostics_overlay(mut commands: Commands) {
+  commands.spawn((
+    DiagnosticsOverlay,
+    Name::new("overlay-diagnostics"),
+    Node {
+      position_type: PositionType::Absolute,
+      top: Val::Px(4.0),
+      right: Val::Px(4.0),
+      ..default()
+    },
+    Visibility::Hidden,
+    GlobalZIndex(i32::MAX),
+    Text::default(),
+    TextFont::from_font_size(14.0),
+    TextColor(Color::WHITE),
+    BackgroundColor(Color::BLACK.with_alpha(0.5)),
+  ));
+}
+
+fn toggle_diagnostics_overlay(
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  mut overlay: Single<&mut Visibility, With<DiagnosticsOverlay>>,
+) {
+  if keyboard_input.just_pressed(KeyCode::F3) {
+    overlay.toggle_visible_hidden();
+  }
+}
+
+fn update_diagnostics_overlay(
+  diagnostics: Res<DiagnosticsStore>,
+  board: Res<BoardRes>,
+  animations: Query<(), With<Animation>>,
+  pops: Query<(), With<Pop>>,
+  overlay: Single<(&mut Text, &Visibility), With<DiagnosticsOverlay>>,
+) {
+  let (mut text, visibility) = overlay.into_inner();
+  if *visibility == Visibility::Hidden {
+    return;
+  }
+  let mut hasher = DefaultHasher::new();
+  board.0.hash(&mut hasher);
+  let smoothed = |path| {
+    diagnostics
+      .get(path)
+      .and_then(|d| d.smoothed())
+      .unwrap_or_default()
+  };
+  text.0 = format!(
+    "fps: {:.0}\nframe time: {:.2} ms\nanimations: {}\nboard: {:016x}",
+    smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+    smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME),
+    animations.iter().len() + pops.iter().len(),
+    hasher.finish(),
+  );
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::state::app::StatesPlugin;
+
+  use super::*;
+  use crate::{BoardPlugin, find_by_name};
+
+  #[test]
+  fn toggle() {
+    let mut app = App::new();
+    app
+      .add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        BoardPlugin::default().without_input(),
+        DiagnosticsOverlayPlugin,
+      ))
+      .init_resource::<ButtonInput<KeyCode>>();
+    app.update();
+    let overlay = |app: &mut App| {
+      let overlay =
+        find_by_name(app.world_mut(), "overlay-diagnostics").unwrap();
+      let overlay = app.world().entity(overlay);
+      (
+        *overlay.get::<Visibility>().unwrap(),
+        overlay.get::<Text>().unwrap().0.clone(),
+      )
+    };
+    assert_eq!(overlay(&mut app), (Visibility::Hidden, String::new()));
+
+    for visibility in [Visibility::Visible, Visibility::Hidden] {
+      let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+      input.clear();
+      input.release(KeyCode::F3);
+      input.press(KeyCode::F3);
+      app.update();
+      let (shown, text) = overlay(&mut app);
+      assert_eq!(shown, visibility);
+      assert!(text.contains("animations: 0"));
+    }
+  }
+}
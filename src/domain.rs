@@ -1,7 +1,14 @@
+use std::collections::VecDeque;
+
+use bevy::reflect::Reflect;
 use rand::prelude::*;
 
+#[cfg(feature = "experimental")]
+pub mod hex;
+
 /// The grid shift direction.
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
   Up,
   Down,
@@ -9,204 +16,1465 @@ pub enum Direction {
   Right,
 }
 
-/// An implementation of 2048 the game.
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct Board<const N: usize>([[u8; N]; N]);
+impl Direction {
+  pub const ALL: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+  ];
+}
+
+/// Directions a [`Board`] can be shifted to, as returned by
+/// [`Board::shiftability`].
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub struct Shiftability {
+  pub can_up: bool,
+  pub can_down: bool,
+  pub can_left: bool,
+  pub can_right: bool,
+}
+
+impl Shiftability {
+  /// Returns `true` if the board can be shifted to `direction`.
+  pub fn can(&self, direction: Direction) -> bool {
+    match direction {
+      Direction::Up => self.can_up,
+      Direction::Down => self.can_down,
+      Direction::Left => self.can_left,
+      Direction::Right => self.can_right,
+    }
+  }
+
+  /// Returns `true` if the board can be shifted to any direction.
+  pub fn any(&self) -> bool {
+    self.can_up || self.can_down || self.can_left || self.can_right
+  }
+
+  /// Returns `true` if the board can be shifted to every direction.
+  pub fn all(&self) -> bool {
+    self.can_up && self.can_down && self.can_left && self.can_right
+  }
+
+  /// Returns an iterator over the directions the board can be shifted to.
+  pub fn directions(self) -> impl Iterator<Item = Direction> {
+    Direction::ALL.into_iter().filter(move |d| self.can(*d))
+  }
+}
+
+/// Cells of a [`Board`] new tiles prefer to spawn on.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub enum SpawnPlacement {
+  /// Any cell, as in the classic game.
+  #[default]
+  Uniform,
+  /// Cells on the first and last rows and columns.
+  EdgesOnly,
+  /// Cells not on the edges.
+  CenterOnly,
+  /// The four corner cells.
+  Corners,
+}
+
+/// Which spawn [`Board::spawn_evaluated`] picks out of every possible one.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SpawnBias {
+  /// The spawn leaving the best rated board, helping the player.
+  Assisted,
+  /// The spawn leaving the worst rated board, making the game harder.
+  Adversarial,
+}
+
+impl SpawnPlacement {
+  /// Returns `true` if the cell at `(row, col)` of a board of `rows` by `cols`
+  /// cells is preferred for spawning.
+  pub fn allows(
+    self,
+    (row, col): (usize, usize),
+    (rows, cols): (usize, usize),
+  ) -> bool {
+    let edge_row = row == 0 || row + 1 == rows;
+    let edge_col = col == 0 || col + 1 == cols;
+    match self {
+      Self::Uniform => true,
+      Self::EdgesOnly => edge_row || edge_col,
+      Self::CenterOnly => !edge_row && !edge_col,
+      Self::Corners => edge_row && edge_col,
+    }
+  }
+}
+
+/// A set of a [`Board`]'s cells, one bit per cell in row-major order, e.g.
+/// the occupied ones as returned by [`Board::occupancy`]. Fits boards of up
+/// to 64 cells.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct OccupancyMask {
+  bits: u64,
+  cols: usize,
+  len: usize,
+}
+
+impl OccupancyMask {
+  /// Returns the number of cells not in the set.
+  pub fn count_empty(&self) -> usize {
+    self.len - self.bits.count_ones() as usize
+  }
+
+  /// Returns the number of cells in the set.
+  pub fn count_occupied(&self) -> usize {
+    self.bits.count_ones() as usize
+  }
+
+  /// Returns `true` if the cell at `row` and `col` is not in the set.
+  pub fn is_empty(&self, row: usize, col: usize) -> bool {
+    self.bits & (1 << (row * self.cols + col)) == 0
+  }
+
+  /// Returns an iterator over row-major indices of cells not in the set, in
+  /// ascending order.
+  pub fn iter_empty_indices(&self) -> impl Iterator<Item = usize> + use<> {
+    let mut empty = self.complement().bits;
+    std::iter::from_fn(move || {
+      let idx = empty.trailing_zeros() as usize;
+      (empty != 0).then(|| {
+        empty &= empty - 1;
+        idx
+      })
+    })
+  }
+
+  /// Returns the row-major index of the `n`th cell, counting from zero, not
+  /// in the set. Returns [`None`] if there are not that many such cells.
+  pub fn nth_empty(&self, n: usize) -> Option<usize> {
+    let mut empty = self.complement().bits;
+    for _ in 0..n {
+      empty &= empty.wrapping_sub(1);
+    }
+    (empty != 0).then(|| empty.trailing_zeros() as usize)
+  }
+
+  /// Returns the cells in both sets.
+  pub fn intersection(self, other: Self) -> Self {
+    Self {
+      bits: self.bits & other.bits,
+      ..self
+    }
+  }
+
+  /// Returns the cells in either set.
+  pub fn union(self, other: Self) -> Self {
+    Self {
+      bits: self.bits | other.bits,
+      ..self
+    }
+  }
+
+  /// Returns the cells of the board not in the set.
+  pub fn complement(self) -> Self {
+    let all = u64::MAX >> (64 - self.len);
+    Self {
+      bits: !self.bits & all,
+      ..self
+    }
+  }
+}
+
+/// Per-cell weights biasing where [`Board::spawn_weighted`] spawns tiles. A
+/// cell is picked with a probability proportional to its weight among the
+/// empty cells.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SpawnWeights<const R: usize, const C: usize = R>([[f32; C]; R]);
+
+impl<const R: usize, const C: usize> SpawnWeights<R, C> {
+  /// Weight of an edge cell in [`edges_favored`](Self::edges_favored)
+  /// weights, relative to the others.
+  pub const EDGE_WEIGHT: f32 = 4.0;
+
+  /// Creates weights from a weight per cell. Fails if any of them is negative
+  /// or not finite, or if they don't add up to a finite total.
+  pub fn new(weights: [[f32; C]; R]) -> Result<Self, SpawnWeightsError> {
+    for (row, weights) in weights.iter().enumerate() {
+      for (col, weight) in weights.iter().enumerate() {
+        if !weight.is_finite() {
+          return Err(SpawnWeightsError::NotFinite((row, col)));
+        }
+        if *weight < 0.0 {
+          return Err(SpawnWeightsError::Negative((row, col)));
+        }
+      }
+    }
+    if !weights.iter().flatten().sum::<f32>().is_finite() {
+      return Err(SpawnWeightsError::TotalNotFinite);
+    }
+    Ok(Self(weights))
+  }
+
+  /// Weighs all cells equally, as in the classic game.
+  pub fn uniform() -> Self {
+    Self([[1.0; C]; R])
+  }
+
+  /// Makes tiles [`EDGE_WEIGHT`](Self::EDGE_WEIGHT) times as likely to spawn
+  /// on the first and last rows and columns as elsewhere.
+  pub fn edges_favored() -> Self {
+    Self(std::array::from_fn(|row| {
+      std::array::from_fn(|col| {
+        if SpawnPlacement::EdgesOnly.allows((row, col), (R, C)) {
+          Self::EDGE_WEIGHT
+        } else {
+          1.0
+        }
+      })
+    }))
+  }
+
+  /// Returns the weight of the cell at `row` and `col`.
+  pub fn get(&self, row: usize, col: usize) -> f32 {
+    self.0[row][col]
+  }
+}
+
+/// Reasons [`SpawnWeights`] can't be created.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpawnWeightsError {
+  /// A cell has a negative weight.
+  Negative((usize, usize)),
+  /// A cell's weight is infinite or NaN.
+  NotFinite((usize, usize)),
+  /// The weights are finite, but their sum overflows to infinity.
+  TotalNotFinite,
+}
+
+impl std::fmt::Display for SpawnWeightsError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Negative(at) => write!(f, "weight at {at:?} is negative"),
+      Self::NotFinite(at) => write!(f, "weight at {at:?} is not finite"),
+      Self::TotalNotFinite => write!(f, "weights add up to infinity"),
+    }
+  }
+}
+
+impl std::error::Error for SpawnWeightsError {}
+
+/// Tiles a new [`Board`] starts with.
+#[derive(Clone, PartialEq, Eq, Default, Debug, Reflect)]
+pub enum InitialLayout {
+  /// Two randomly spawned tiles, as in the classic game.
+  #[default]
+  Standard,
+  /// The given number of randomly spawned tiles.
+  Random(usize),
+  /// Tiles with the given exponents at the given cells.
+  Fixed(Vec<((usize, usize), u8)>),
+}
+
+/// The reason an [`InitialLayout`] can't be used for a board.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LayoutError {
+  /// More random tiles requested than the board has cells.
+  TooManyTiles(usize),
+  /// A fixed tile lies outside of the board.
+  OutOfBounds((usize, usize)),
+  /// Two fixed tiles share a cell.
+  Overlapping((usize, usize)),
+  /// A fixed tile has a zero exponent, i.e. it's empty.
+  EmptyTile((usize, usize)),
+}
+
+impl std::fmt::Display for LayoutError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::TooManyTiles(n) => write!(f, "{n} tiles don't fit on the board"),
+      Self::OutOfBounds(at) => write!(f, "tile at {at:?} is out of bounds"),
+      Self::Overlapping(at) => write!(f, "more than one tile at {at:?}"),
+      Self::EmptyTile(at) => write!(f, "tile at {at:?} is empty"),
+    }
+  }
+}
+
+impl std::error::Error for LayoutError {}
+
+/// The reason a [`Board`] can't be made of the given cells.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BoardError {
+  /// The exponent at a cell is above [`MAX_EXPONENT`].
+  ExponentTooLarge((usize, usize)),
+}
+
+impl std::fmt::Display for BoardError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::ExponentTooLarge(at) => {
+        write!(f, "exponent at {at:?} is above {MAX_EXPONENT}")
+      }
+    }
+  }
+}
+
+impl std::error::Error for BoardError {}
+
+/// The reason a [`Board`] can't be parsed from a string.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ParseBoardError {
+  /// The string has the given number of non-blank lines instead of one per
+  /// row.
+  RowCount(usize),
+  /// The row with the given index doesn't have a value per column.
+  ColumnCount(usize),
+  /// A value isn't a number, nor `.` for an empty cell.
+  NotANumber((usize, usize)),
+  /// A value is neither 0 nor a power of two from 2 up to 2^[`MAX_EXPONENT`].
+  NotATile((usize, usize)),
+}
+
+impl std::fmt::Display for ParseBoardError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::RowCount(n) => write!(f, "wrong number of rows: {n}"),
+      Self::ColumnCount(row) => {
+        write!(f, "wrong number of values in row {row}")
+      }
+      Self::NotANumber(at) => write!(f, "value at {at:?} is not a number"),
+      Self::NotATile(at) => {
+        write!(f, "value at {at:?} is not a power of two a tile can hold")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ParseBoardError {}
+
+const TWO_TO_FOUR_SPAWN_CHANCE: f64 = 90.0; // %
+
+/// Returns the exponent of a freshly spawned tile: 1 or, less likely, 2.
+fn random_spawn_value(rng: &mut impl Rng) -> u8 {
+  if rng.random_bool(TWO_TO_FOUR_SPAWN_CHANCE / 100.0) {
+    1
+  } else {
+    2
+  }
+}
+
+/// The largest exponent whose tile value fits a `u32`.
+pub const MAX_EXPONENT: u8 = 31;
+
+/// The exponent of the 2048 tile winning the classic game.
+pub const DEFAULT_WIN_EXPONENT: u8 = 11;
+
+/// An implementation of 2048 the game on a board of `R` rows and `C` columns,
+/// square unless `C` is given.
+///
+/// Boards are hashable and ordered lexicographically over their exponents in
+/// row-major order, so they can be collected into sets or used as map keys.
+/// The ordering is guaranteed to stay this way.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Reflect)]
+pub struct Board<const R: usize, const C: usize = R>(pub(crate) [[u8; C]; R]);
+
+/// A [`Board`] of `N` rows and `N` columns, spelled out.
+pub type SquareBoard<const N: usize> = Board<N, N>;
+
+/// How a [`Board`] is serialized: its dimensions followed by its exponents
+/// in row-major order, so that any size round-trips.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Board")]
+struct SerdeBoard {
+  rows: usize,
+  cols: usize,
+  cells: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl<const R: usize, const C: usize> serde::Serialize for Board<R, C> {
+  fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    SerdeBoard {
+      rows: R,
+      cols: C,
+      cells: self.iter_numbers().collect(),
+    }
+    .serialize(s)
+  }
+}
+
+/// Fails on a board of other dimensions rather than resizing it, and on
+/// exponents [`Board::from_rows`] rejects.
+#[cfg(feature = "serde")]
+impl<'de, const R: usize, const C: usize> serde::Deserialize<'de>
+  for Board<R, C>
+{
+  fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+    use serde::de::Error;
+
+    let board = SerdeBoard::deserialize(d)?;
+    if (board.rows, board.cols) != (R, C) {
+      return Err(D::Error::custom(format!(
+        "expected a {R}x{C} board, got a {}x{} one",
+        board.rows, board.cols
+      )));
+    }
+    if board.cells.len() != R * C {
+      return Err(D::Error::invalid_length(
+        board.cells.len(),
+        &format!("{} cells", R * C).as_str(),
+      ));
+    }
+    let mut cells = board.cells.into_iter();
+    Self::from_rows(std::array::from_fn(|_| {
+      std::array::from_fn(|_| cells.next().unwrap_or(0))
+    }))
+    .map_err(D::Error::custom)
+  }
+}
+
+/// Same as [`Board::new`].
+impl<const R: usize, const C: usize> Default for Board<R, C> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<const R: usize, const C: usize> Board<R, C> {
+  /// Creates an empty 2048 board.
+  pub fn empty() -> Self {
+    Self([[0; C]; R])
+  }
+
+  /// Creates a board of the given `rows` of exponents, 0 for an empty cell.
+  /// Fails on the first cell, in row-major order, holding an exponent above
+  /// [`MAX_EXPONENT`].
+  #[doc(alias = "from_exponents")]
+  pub fn from_rows(rows: [[u8; C]; R]) -> Result<Self, BoardError> {
+    for (row, exponents) in rows.iter().enumerate() {
+      if let Some(col) = exponents.iter().position(|n| *n > MAX_EXPONENT) {
+        return Err(BoardError::ExponentTooLarge((row, col)));
+      }
+    }
+    Ok(Self(rows))
+  }
+
+  /// Like [`from_rows`](Self::from_rows), but takes any exponents. Tiles
+  /// above [`MAX_EXPONENT`] still shift and merge, but their values can't be
+  /// shown.
+  pub fn from_rows_unchecked(rows: [[u8; C]; R]) -> Self {
+    Self(rows)
+  }
+
+  /// Creates an new 2048 board and [`spawn`](Self::spawn)s two numbers on it.
+  pub fn new() -> Self {
+    Self::new_with_tiles(2)
+  }
+
+  /// Like [`new`](Self::new), but spawns from `rng`, so that a seeded one
+  /// always makes the same board.
+  pub fn new_with_rng(rng: &mut impl Rng) -> Self {
+    let mut board = Self::empty();
+    board.spawn_with_rng(rng);
+    board.spawn_with_rng(rng);
+    board
+  }
+
+  /// Like [`new`](Self::new), but always makes the same board for the same
+  /// `seed`.
+  pub fn new_seeded(seed: u64) -> Self {
+    Self::new_with_rng(&mut rand::rngs::StdRng::seed_from_u64(seed))
+  }
+
+  /// Creates an new 2048 board and [`spawn`](Self::spawn)s `count` numbers on
+  /// it, or as many as fit.
+  pub fn new_with_tiles(count: usize) -> Self {
+    let mut board = Self::empty();
+    for _ in 0..count.min(R * C) {
+      board.spawn();
+    }
+    board
+  }
+
+  /// Creates a board from a code of `R * C` base 36 digits, one exponent per
+  /// cell in row-major order, e.g. `"0012001000000003"` for a 4x4 board.
+  /// Returns [`None`] if the code doesn't describe a board of this size or
+  /// has an exponent [`from_rows`](Self::from_rows) rejects, i.e. a digit
+  /// above `v`.
+  pub fn from_code(code: &str) -> Option<Self> {
+    let code = code.trim();
+    if code.chars().count() != R * C {
+      return None;
+    }
+    let mut rows = [[0; C]; R];
+    for (idx, c) in code.chars().enumerate() {
+      rows[idx / C][idx % C] = c.to_digit(36)? as u8;
+    }
+    Self::from_rows(rows).ok()
+  }
+
+  /// Creates a full board of exponents increasing in row-major order from
+  /// `start_exponent`, so nothing on it can merge. Exponents wrap around from
+  /// [`MAX_EXPONENT`] back to 1, skipping the empty 0, so every tile is valid
+  /// and they're distinct on boards of up to 31 cells. Past that they repeat,
+  /// but never next to each other unless there are a multiple of 31 columns.
+  /// A `start_exponent` of 0 starts at 1 as well.
+  pub fn fill_distinct(start_exponent: u8) -> Self {
+    let start = start_exponent.max(1) as usize - 1;
+    let mut board = Self::empty();
+    for idx in 0..R * C {
+      board.set(
+        idx / C,
+        idx % C,
+        ((start + idx) % MAX_EXPONENT as usize) as u8 + 1,
+      );
+    }
+    board
+  }
+
+  /// Creates a new 2048 board with the given `layout` of starting tiles.
+  pub fn with_layout(layout: &InitialLayout) -> Result<Self, LayoutError> {
+    match layout {
+      InitialLayout::Standard => Ok(Self::new()),
+      InitialLayout::Random(n) => {
+        if *n > R * C {
+          return Err(LayoutError::TooManyTiles(*n));
+        }
+        Ok(Self::new_with_tiles(*n))
+      }
+      InitialLayout::Fixed(tiles) => {
+        let mut board = Self::empty();
+        for &((row, col), num) in tiles {
+          if row >= R || col >= C {
+            return Err(LayoutError::OutOfBounds((row, col)));
+          }
+          if num == 0 {
+            return Err(LayoutError::EmptyTile((row, col)));
+          }
+          if board.get(row, col) != 0 {
+            return Err(LayoutError::Overlapping((row, col)));
+          }
+          board.set(row, col, num);
+        }
+        Ok(board)
+      }
+    }
+  }
+
+  /// Returns the number of the board's rows and columns.
+  pub fn size(&self) -> (usize, usize) {
+    (R, C)
+  }
+
+  /// Returns a flat iterator over board's numbers.
+  pub fn iter_numbers(&self) -> impl Iterator<Item = u8> {
+    self.0.iter().flatten().cloned()
+  }
+
+  /// Returns every cell of the board along with its number, in row-major
+  /// order.
+  pub fn iter_cells(&self) -> impl Iterator<Item = ((usize, usize), u8)> {
+    self
+      .iter_numbers()
+      .enumerate()
+      .map(|(idx, n)| ((idx / C, idx % C), n))
+  }
+
+  /// Like [`iter_cells`](Self::iter_cells), but skips empty cells.
+  pub fn iter_nonempty_cells(
+    &self,
+  ) -> impl Iterator<Item = ((usize, usize), u8)> {
+    self.iter_cells().filter(|(_, n)| *n != 0)
+  }
+
+  /// Returns the set of occupied cells, computed in a single pass over the
+  /// board. Only available for boards of up to 64 cells.
+  pub fn occupancy(&self) -> OccupancyMask {
+    const { assert!(R * C <= 64, "occupancy masks fit up to 64 cells") };
+    self.fitting_occupancy().unwrap()
+  }
+
+  /// Like [`occupancy`](Self::occupancy), but returns [`None`] for boards of
+  /// more than 64 cells instead of failing to compile for them.
+  fn fitting_occupancy(&self) -> Option<OccupancyMask> {
+    (R * C <= 64).then(|| OccupancyMask {
+      bits: self
+        .iter_numbers()
+        .enumerate()
+        .fold(0, |bits, (idx, n)| bits | ((n != 0) as u64) << idx),
+      cols: C,
+      len: R * C,
+    })
+  }
+
+  /// Returns the row-major indices of the empty cells in ascending order,
+  /// read off the [`occupancy`](Self::occupancy) mask. Boards of more than
+  /// 64 cells, which don't fit one, are scanned cell by cell instead.
+  fn empty_indices(&self) -> impl Iterator<Item = usize> {
+    let mask = self.fitting_occupancy();
+    let scan = mask.is_none().then(|| {
+      self
+        .iter_numbers()
+        .enumerate()
+        .filter_map(|(idx, n)| (n == 0).then_some(idx))
+    });
+    mask
+      .into_iter()
+      .flat_map(|mask| mask.iter_empty_indices())
+      .chain(scan.into_iter().flatten())
+  }
+
+  /// Returns the number of empty cells.
+  pub fn count_empty(&self) -> usize {
+    match self.fitting_occupancy() {
+      Some(mask) => mask.count_empty(),
+      None => self.empty_indices().count(),
+    }
+  }
+
+  /// Returns `true` if no cell is empty, so nothing can spawn.
+  pub fn is_full(&self) -> bool {
+    self.count_empty() == 0
+  }
+
+  /// Returns the number of cells holding the given `exponent`.
+  pub fn count_value(&self, exponent: u8) -> usize {
+    self.iter_numbers().filter(|n| *n == exponent).count()
+  }
+
+  /// Returns `true` if a tile with the given `exponent` or a larger one is
+  /// on the board.
+  pub fn has_reached(&self, exponent: u8) -> bool {
+    let max = self.max_tile();
+    max != 0 && max >= exponent
+  }
+
+  /// Returns the largest exponent on the board, 0 if it's empty.
+  pub fn max_tile(&self) -> u8 {
+    self.iter_numbers().max().unwrap_or(0)
+  }
+
+  /// Returns `true` if the game on the board is won, that is, if it
+  /// [has reached](Self::has_reached) `target_exponent`.
+  pub fn is_won(&self, target_exponent: u8) -> bool {
+    self.has_reached(target_exponent)
+  }
+
+  /// Returns `true` if the board holds the 2048 tile or a larger one, see
+  /// [`DEFAULT_WIN_EXPONENT`].
+  pub fn is_won_default(&self) -> bool {
+    self.is_won(DEFAULT_WIN_EXPONENT)
+  }
+
+  /// Returns a value from the board.
+  pub fn get(&self, row: usize, col: usize) -> u8 {
+    self.0[row][col]
+  }
+
+  /// Returns the orthogonal neighbors of the cell at `row` and `col` that lie
+  /// on the board, with their values, in the order up, down, left, right.
+  pub fn neighbors(
+    &self,
+    row: usize,
+    col: usize,
+  ) -> impl Iterator<Item = ((usize, usize), u8)> {
+    [
+      row.checked_sub(1).map(|r| (r, col)),
+      (row + 1 < R).then_some((row + 1, col)),
+      col.checked_sub(1).map(|c| (row, c)),
+      (col + 1 < C).then_some((row, col + 1)),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|(r, c)| ((r, c), self.get(r, c)))
+  }
+
+  /// Sets a value on the board.
+  fn set(&mut self, row: usize, col: usize, num: u8) {
+    self.0[row][col] = num;
+  }
+
+  /// Tries to add a 2 or 4 value to the board. Returns [`Some`] coordinates of
+  /// spawned value on success, [`None`] otherwise.
+  pub fn spawn(&mut self) -> Option<(u8, (usize, usize))> {
+    self.spawn_with(SpawnPlacement::Uniform)
+  }
+
+  /// Like [`spawn`](Self::spawn), but rolls the cell and the value with
+  /// `rng`, so that a seeded one spawns the same tiles on the same boards.
+  pub fn spawn_with_rng(
+    &mut self,
+    rng: &mut impl Rng,
+  ) -> Option<(u8, (usize, usize))> {
+    self.spawn_placed(SpawnPlacement::Uniform, rng)
+  }
+
+  /// Like [`spawn`](Self::spawn), but only spawns on empty cells allowed by
+  /// `placement`, if there are any. Falls back to any empty cell otherwise.
+  pub fn spawn_with(
+    &mut self,
+    placement: SpawnPlacement,
+  ) -> Option<(u8, (usize, usize))> {
+    self.spawn_placed(placement, &mut rand::rng())
+  }
+
+  fn spawn_placed(
+    &mut self,
+    placement: SpawnPlacement,
+    rng: &mut impl Rng,
+  ) -> Option<(u8, (usize, usize))> {
+    let idx = self.pick_empty(placement, rng)?;
+    let (row, col) = (idx / C, idx % C);
+    let num = random_spawn_value(rng);
+    self.set(row, col, num);
+    Some((num, (row, col)))
+  }
+
+  /// Returns the row-major index of a uniformly random empty cell allowed by
+  /// `placement`, or of any empty cell if none is, without allocating. On
+  /// boards that fit an [`OccupancyMask`] the cell is the `k`th empty bit of
+  /// the mask, with the cells `placement` rules out masked as occupied.
+  /// Larger boards are counted and then scanned up to the `k`th candidate.
+  fn pick_empty(
+    &self,
+    placement: SpawnPlacement,
+    rng: &mut impl Rng,
+  ) -> Option<usize> {
+    let allowed = |idx: usize| placement.allows((idx / C, idx % C), (R, C));
+    if let Some(occupied) = self.fitting_occupancy() {
+      let ruled_out = OccupancyMask {
+        bits: (0..R * C)
+          .filter(|&idx| !allowed(idx))
+          .fold(0, |bits, idx| bits | 1 << idx),
+        ..occupied
+      };
+      let mut pick = |mask: OccupancyMask| {
+        let count = mask.count_empty();
+        (count > 0)
+          .then(|| mask.nth_empty(rng.random_range(0..count)))
+          .flatten()
+      };
+      return pick(occupied.union(ruled_out)).or_else(|| pick(occupied));
+    }
+    let mut pick = |preferred: bool| {
+      let candidate =
+        |&(idx, n): &(usize, u8)| n == 0 && (!preferred || allowed(idx));
+      let cells = || self.iter_numbers().enumerate().filter(candidate);
+      let count = cells().count();
+      (count > 0)
+        .then(|| cells().nth(rng.random_range(0..count)))
+        .flatten()
+        .map(|(idx, _)| idx)
+    };
+    pick(true).or_else(|| pick(false))
+  }
+
+  /// Like [`spawn`](Self::spawn), but picks the cell with a probability
+  /// proportional to its weight among the empty cells. Falls back to any
+  /// empty cell if all of them weigh zero.
+  pub fn spawn_weighted(
+    &mut self,
+    weights: &SpawnWeights<R, C>,
+  ) -> Option<(u8, (usize, usize))> {
+    let weight = |idx: usize| weights.get(idx / C, idx % C);
+    let total = self.empty_indices().map(weight).sum::<f32>();
+    if total <= 0.0 {
+      return self.spawn();
+    }
+    let mut rng = rand::rng();
+    let mut left = rng.random_range(0.0..total);
+    let mut picked = None;
+    for idx in self.empty_indices().filter(|&i| weight(i) > 0.0) {
+      // the last candidate is kept should rounding leave some weight over
+      picked = Some(idx);
+      left -= weight(idx);
+      if left < 0.0 {
+        break;
+      }
+    }
+    let idx = picked?;
+    let (row, col) = (idx / C, idx % C);
+    let num = random_spawn_value(&mut rng);
+    self.set(row, col, num);
+    Some((num, (row, col)))
+  }
+
+  /// Like [`spawn`](Self::spawn), but picks the cell and value instead of
+  /// rolling them: out of every spawn possible, the one leaving the board
+  /// `evaluate` rates highest or lowest, as `bias` says. Ties go to the first
+  /// empty cell in row-major order, and to a 2 over a 4.
+  pub fn spawn_evaluated(
+    &mut self,
+    bias: SpawnBias,
+    evaluate: impl Fn(&Self) -> f32,
+  ) -> Option<(u8, (usize, usize))> {
+    let mut best: Option<(f32, u8, usize)> = None;
+    for idx in self.empty_indices() {
+      for num in [1, 2] {
+        let mut board = self.clone();
+        board.set(idx / C, idx % C, num);
+        let rating = evaluate(&board);
+        let better = best.is_none_or(|(best, ..)| match bias {
+          SpawnBias::Assisted => rating > best,
+          SpawnBias::Adversarial => rating < best,
+        });
+        if better {
+          best = Some((rating, num, idx));
+        }
+      }
+    }
+    let (_, num, idx) = best?;
+    let (row, col) = (idx / C, idx % C);
+    self.set(row, col, num);
+    Some((num, (row, col)))
+  }
+
+  /// Returns the best rating `evaluate` gives a board the player can shift
+  /// this one into, looking `depth` moves ahead and assuming every spawn in
+  /// between is the worst one for the player. Returns negative infinity if
+  /// the board can't be shifted. Rating the spawns of
+  /// [`spawn_evaluated`](Self::spawn_evaluated) with this makes an
+  /// adversarial spawner that accounts for the player's reply.
+  pub fn best_reply(&self, depth: u8, evaluate: &impl Fn(&Self) -> f32) -> f32 {
+    let mut best = f32::NEG_INFINITY;
+    for direction in Direction::ALL {
+      let mut board = self.clone();
+      if board.shift(direction).is_empty() {
+        continue;
+      }
+      let rating = if depth <= 1 {
+        evaluate(&board)
+      } else {
+        board
+          .all_spawns()
+          .map(|(spawned, _)| spawned.best_reply(depth - 1, evaluate))
+          .fold(f32::INFINITY, f32::min)
+      };
+      best = best.max(rating);
+    }
+    best
+  }
+
+  /// Returns every board [`spawn`](Self::spawn) can produce, paired with the
+  /// probability of it being produced. Yields nothing for a full board.
+  pub fn all_spawns(&self) -> impl Iterator<Item = (Self, f64)> {
+    let empty = self.count_empty() as f64;
+    let two = TWO_TO_FOUR_SPAWN_CHANCE / 100.0;
+    self.empty_indices().flat_map(move |idx| {
+      [(1, two), (2, 1.0 - two)].map(|(num, chance)| {
+        let mut board = self.clone();
+        board.set(idx / C, idx % C, num);
+        (board, chance / empty)
+      })
+    })
+  }
+
+  /// Returns the directions [`Board`] can be shifted to, found in a single
+  /// pass over pairs of adjacent cells.
+  pub fn shiftability(&self) -> Shiftability {
+    // a tile can go from `a` toward `b` if `b` is empty or equal to it
+    let movable = |a: u8, b: u8| a != 0 && (b == 0 || a == b);
+    let mut shiftability = Shiftability::default();
+    for i in 0..R {
+      for j in 0..C {
+        let it = self.0[i][j];
+        if j + 1 < C {
+          let right = self.0[i][j + 1];
+          shiftability.can_right |= movable(it, right);
+          shiftability.can_left |= movable(right, it);
+        }
+        if i + 1 < R {
+          let down = self.0[i + 1][j];
+          shiftability.can_down |= movable(it, down);
+          shiftability.can_up |= movable(down, it);
+        }
+      }
+      if shiftability.all() {
+        break;
+      }
+    }
+    shiftability
+  }
+
+  /// Returns `true` if [`Board`] can be shifted to any direction, `false`
+  /// otherwise.
+  pub fn is_shiftable(&self) -> bool {
+    self.shiftability().any()
+  }
+
+  /// Returns the directions [`Board`] can be shifted to.
+  pub fn legal_moves(&self) -> Vec<Direction> {
+    self.shiftability().directions().collect()
+  }
+
+  /// Returns `true` if one of the board's corners holds its largest tile.
+  /// An empty board has no largest tile.
+  pub fn max_in_corner(&self) -> bool {
+    let max = self.max_tile();
+    max != 0
+      && [(0, 0), (0, C - 1), (R - 1, 0), (R - 1, C - 1)]
+        .into_iter()
+        .any(|(row, col)| self.get(row, col) == max)
+  }
+
+  /// Rates how close the board is to the "snake" pattern, in which values
+  /// decrease along a path winding back and forth through the rows (or
+  /// columns) from one of the corners. Returns the share of cells, from `0.0`
+  /// to `1.0`, that already hold the value they'd hold if the board's values
+  /// were sorted along the best fitting such path.
+  pub fn is_solved_snake(&self) -> f32 {
+    let mut sorted = self.iter_numbers().collect::<Vec<_>>();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    let mut best = 0;
+    for transpose in [false, true] {
+      for flip_rows in [false, true] {
+        for flip_cols in [false, true] {
+          let matching = (0..R * C)
+            .filter(|&i| {
+              let (mut row, mut col) = if transpose {
+                let (row, col) = (i % R, i / R);
+                (if col % 2 == 1 { R - 1 - row } else { row }, col)
+              } else {
+                let (row, col) = (i / C, i % C);
+                (row, if row % 2 == 1 { C - 1 - col } else { col })
+              };
+              if flip_rows {
+                row = R - 1 - row;
+              }
+              if flip_cols {
+                col = C - 1 - col;
+              }
+              self.0[row][col] == sorted[i]
+            })
+            .count();
+          best = best.max(matching);
+        }
+      }
+    }
+    best as f32 / (R * C) as f32
+  }
+
+  /// Returns the number of pairs of adjacent tiles holding the same value,
+  /// that is, merges available on the board.
+  pub fn mergeable_pairs(&self) -> usize {
+    let mut pairs = 0;
+    for i in 0..R {
+      for j in 0..C {
+        let it = self.0[i][j];
+        if it == 0 {
+          continue;
+        }
+        pairs += (j + 1 < C && self.0[i][j + 1] == it) as usize;
+        pairs += (i + 1 < R && self.0[i + 1][j] == it) as usize;
+      }
+    }
+    pairs
+  }
+
+  /// Roughly rates how hard the position is to play on, from `0.0` for an
+  /// empty board to `1.0` for a stuck one. Computed as
+  ///
+  /// `(1 - empty) * (0.6 + 0.25 * (1 - mergeable) + 0.15 * confined)`
+  ///
+  /// where `empty` is the share of empty cells, `mergeable` is the number of
+  /// [mergeable pairs](Self::mergeable_pairs) relative to the shorter side of
+  /// the board, capped at `1.0`, and `confined` is the share of the largest
+  /// tile's neighbors it can neither move onto nor merge with.
+  pub fn difficulty_estimate(&self) -> f32 {
+    let cells = (R * C) as f32;
+    let empty = self.count_empty() as f32 / cells;
+    let mergeable = (self.mergeable_pairs() as f32 / R.min(C) as f32).min(1.0);
+    let max = self.max_tile();
+    let confined = match self.iter_cells().find(|(_, n)| *n == max) {
+      Some(((row, col), _)) if max != 0 => {
+        let (blocked, total) =
+          self
+            .neighbors(row, col)
+            .fold((0, 0), |(blocked, total), (_, n)| {
+              (blocked + (n != 0 && n != max) as usize, total + 1)
+            });
+        if total == 0 {
+          0.0
+        } else {
+          blocked as f32 / total as f32
+        }
+      }
+      _ => 0.0,
+    };
+    (1.0 - empty) * (0.6 + 0.25 * (1.0 - mergeable) + 0.15 * confined)
+  }
+
+  /// Returns the [TileAction]s shifting the board to `direction` would take,
+  /// leaving the board as it is.
+  pub fn peek_shift(&self, direction: Direction) -> Vec<TileAction> {
+    self.clone().shift(direction)
+  }
+
+  /// Moves values on the board to given `direction` and returns [TileAction]s
+  /// that were taken to update the board.
+  pub fn shift(&mut self, direction: Direction) -> Vec<TileAction> {
+    let mut actions = Vec::new();
+    let reversed = matches!(direction, Direction::Right | Direction::Down);
+    match direction {
+      Direction::Left | Direction::Right => {
+        for (i, row) in self.0.iter_mut().enumerate() {
+          if reversed {
+            row.reverse();
+          }
+          let lane = shift_lane(row, &ClassicMerge);
+          if reversed {
+            row.reverse();
+          }
+          let cell = |j| (i, if reversed { C - 1 - j } else { j });
+          actions.extend(lane.into_iter().map(|a| a.on_board(cell)));
+        }
+      }
+      Direction::Up | Direction::Down => {
+        for j in 0..C {
+          let mut col: [u8; R] = std::array::from_fn(|i| self.0[i][j]);
+          if reversed {
+            col.reverse();
+          }
+          let lane = shift_lane(&mut col, &ClassicMerge);
+          if reversed {
+            col.reverse();
+          }
+          for (i, n) in col.into_iter().enumerate() {
+            self.0[i][j] = n;
+          }
+          let cell = |i| (if reversed { R - 1 - i } else { i }, j);
+          actions.extend(lane.into_iter().map(|a| a.on_board(cell)));
+        }
+      }
+    }
+    actions
+  }
+}
+
+/// Draws the board as rows of tile values, with `.` for empty cells, right
+/// aligned to the widest value on the board.
+impl<const R: usize, const C: usize> std::fmt::Display for Board<R, C> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let cells = self
+      .iter_numbers()
+      .map(|n| match n {
+        0 => ".".to_string(),
+        n => 1u64
+          .checked_shl(n.into())
+          .map_or_else(|| format!("2^{n}"), |value| value.to_string()),
+      })
+      .collect::<Vec<_>>();
+    let width = cells.iter().map(String::len).max().unwrap_or(0);
+    for row in cells.chunks(C.max(1)) {
+      let row = row
+        .iter()
+        .map(|cell| format!("{cell:>width$}"))
+        .collect::<Vec<_>>();
+      writeln!(f, "{}", row.join(" "))?;
+    }
+    Ok(())
+  }
+}
+
+/// Parses rows of tile values separated by whitespace, one row per line, with
+/// 0 or `.` for empty cells. Blank lines are skipped, so the output of
+/// [`Display`](std::fmt::Display) parses back to the same board.
+impl<const R: usize, const C: usize> std::str::FromStr for Board<R, C> {
+  type Err = ParseBoardError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let lines = s.lines().filter(|line| !line.trim().is_empty());
+    if lines.clone().count() != R {
+      return Err(ParseBoardError::RowCount(lines.count()));
+    }
+    let mut board = Self::empty();
+    for (row, line) in lines.enumerate() {
+      if line.split_whitespace().count() != C {
+        return Err(ParseBoardError::ColumnCount(row));
+      }
+      for (col, value) in line.split_whitespace().enumerate() {
+        let at = (row, col);
+        let value = match value {
+          "." => 0,
+          value => value
+            .parse::<u32>()
+            .map_err(|_| ParseBoardError::NotANumber(at))?,
+        };
+        match value {
+          0 => {}
+          2.. if value.is_power_of_two() => {
+            board.set(row, col, value.trailing_zeros() as u8);
+          }
+          _ => return Err(ParseBoardError::NotATile(at)),
+        }
+      }
+    }
+    Ok(board)
+  }
+}
+
+/// A [`Board`] along with the score of the game played on it. A merge scores
+/// the value of the tile it makes, e.g. 8 for two 4s. The score is kept out
+/// of the board itself so boards compare and hash by their tiles alone.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ScoredBoard<const R: usize, const C: usize = R> {
+  board: Board<R, C>,
+  score: u32,
+}
+
+impl<const R: usize, const C: usize> Default for ScoredBoard<R, C> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<const R: usize, const C: usize> From<Board<R, C>> for ScoredBoard<R, C> {
+  /// Starts scoring a game from `board`, with no score yet.
+  fn from(board: Board<R, C>) -> Self {
+    Self { board, score: 0 }
+  }
+}
+
+impl<const R: usize, const C: usize> ScoredBoard<R, C> {
+  /// Returns an empty board with no score.
+  pub fn empty() -> Self {
+    Board::empty().into()
+  }
+
+  /// Returns a [new](Board::new) board with no score.
+  pub fn new() -> Self {
+    Board::new().into()
+  }
+
+  pub fn board(&self) -> &Board<R, C> {
+    &self.board
+  }
+
+  pub fn score(&self) -> u32 {
+    self.score
+  }
+
+  /// [Shifts](Board::shift) the board and adds what its merges score.
+  pub fn shift(&mut self, direction: Direction) -> Vec<TileAction> {
+    let actions = self.board.shift(direction);
+    let points = actions.iter().map(TileAction::points);
+    self.score = points.fold(self.score, u32::saturating_add);
+    actions
+  }
+
+  /// [Spawns](Board::spawn) a tile, which doesn't score anything.
+  pub fn spawn(&mut self) -> Option<(u8, (usize, usize))> {
+    self.board.spawn()
+  }
+}
+
+/// A [`Board`] remembering its states before the latest shifts, so that they
+/// can be [undone](Self::undo). Only the last
+/// [`history_depth`](Self::history_depth) shifts are kept.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UndoableBoard<const R: usize, const C: usize = R> {
+  board: Board<R, C>,
+  history: VecDeque<Board<R, C>>,
+  history_depth: Option<usize>,
+}
+
+impl<const R: usize, const C: usize> Default for UndoableBoard<R, C> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<const R: usize, const C: usize> From<Board<R, C>> for UndoableBoard<R, C> {
+  /// Starts a game from `board`, with nothing to undo yet.
+  fn from(board: Board<R, C>) -> Self {
+    Self {
+      board,
+      history: VecDeque::new(),
+      history_depth: Some(Self::DEFAULT_HISTORY_DEPTH),
+    }
+  }
+}
 
-impl<const N: usize> Board<N> {
-  const TWO_TO_FOUR_SPAWN_CHANCE: f64 = 90.0; // %
+impl<const R: usize, const C: usize> UndoableBoard<R, C> {
+  pub const DEFAULT_HISTORY_DEPTH: usize = 8;
 
-  /// Creates an empty 2048 board.
+  /// Returns an empty board with nothing to undo.
   pub fn empty() -> Self {
-    Self([[0; N]; N])
+    Board::empty().into()
   }
 
-  /// Creates an new 2048 board and [`spawn`](Self::spawn)s two numbers on it.
+  /// Returns a [new](Board::new) board with nothing to undo.
   pub fn new() -> Self {
-    let mut board = Self::empty();
-    board.spawn();
-    board.spawn();
-    board
+    Board::new().into()
   }
 
-  /// Returns the size of the board's side.
-  pub fn size(&self) -> usize {
-    N
+  /// Sets how many shifts can be undone, or makes it unlimited with [`None`].
+  /// Forgets the oldest shifts if more have been made already.
+  pub fn with_history_depth(mut self, depth: Option<usize>) -> Self {
+    self.history_depth = depth;
+    self.trim_history();
+    self
   }
 
-  /// Returns a flat iterator over board's numbers.
-  pub fn iter_numbers(&self) -> impl Iterator<Item = u8> {
-    self.0.iter().flatten().cloned()
+  pub fn board(&self) -> &Board<R, C> {
+    &self.board
   }
 
-  /// Returns a value from the board.
-  pub fn get(&self, row: usize, col: usize) -> u8 {
-    self.0[row][col]
+  pub fn history_depth(&self) -> Option<usize> {
+    self.history_depth
   }
 
-  /// Sets a value on the board.
-  fn set(&mut self, row: usize, col: usize, num: u8) {
-    self.0[row][col] = num;
+  /// Returns the number of shifts that can be undone.
+  pub fn history_len(&self) -> usize {
+    self.history.len()
   }
 
-  /// Tries to add a 2 or 4 value to the board. Returns [`Some`] coordinates of
-  /// spawned value on success, [`None`] otherwise.
+  /// [Shifts](Board::shift) the board, remembering its state before the shift
+  /// if it changed anything.
+  pub fn shift(&mut self, direction: Direction) -> Vec<TileAction> {
+    let before = self.board.clone();
+    let actions = self.board.shift(direction);
+    if !actions.is_empty() {
+      self.history.push_back(before);
+      self.trim_history();
+    }
+    actions
+  }
+
+  /// [Spawns](Board::spawn) a tile. It's taken back along with the shift
+  /// before it.
   pub fn spawn(&mut self) -> Option<(u8, (usize, usize))> {
-    let coords = self
-      .iter_numbers()
-      .enumerate()
-      .filter_map(|(i, v)| v.eq(&0).then_some(i))
-      .choose(&mut rand::rng())
-      .map(|idx| (idx / N, idx % N));
-    let (row, col) = coords?;
-    let num = if rand::random_bool(Self::TWO_TO_FOUR_SPAWN_CHANCE / 100.0) {
-      1
-    } else {
-      2
-    };
-    self.set(row, col, num);
-    coords.map(|c| (num, c))
+    self.board.spawn()
   }
 
-  /// Returns `true` if [`Board`] can be shifted to any direction, `false`
-  /// otherwise.
-  pub fn is_shiftable(&self) -> bool {
-    if self.0[0][0] == 0 {
-      return true;
-    }
-    for i in 0..N - 1 {
-      for j in 0..N {
-        let (it, down) = (self.0[i][j], self.0[i + 1][j]);
-        if down == 0 || it == down {
-          return true;
-        }
-        let (it, right) = (self.0[j][i], self.0[j][i + 1]);
-        if right == 0 || it == right {
-          return true;
-        }
+  /// Restores the board to what it was before the latest remembered shift,
+  /// dropping whatever has spawned since. Returns `false` if there's nothing
+  /// to undo.
+  pub fn undo(&mut self) -> bool {
+    match self.history.pop_back() {
+      Some(board) => {
+        self.board = board;
+        true
       }
+      None => false,
     }
-    false
   }
 
-  /// Moves values on the board to given `direction` and returns [TileAction]s
-  /// that were taken to update the board.
-  pub fn shift(&mut self, direction: Direction) -> Vec<TileAction> {
-    match direction {
-      Direction::Left => self
-        .0
-        .iter_mut()
-        .enumerate()
-        .flat_map(|(idx, row)| Self::shift_nums_left(row.each_mut(), idx))
-        .collect(),
-      Direction::Right => self
-        .0
-        .iter_mut()
-        .enumerate()
-        .flat_map(|(idx, row)| {
-          let mut row_rev = row.each_mut();
-          row_rev.reverse();
-          Self::shift_nums_left(row_rev, idx)
-            .into_iter()
-            .map(|a| TileAction {
-              from: (a.from.0, N - 1 - a.from.1),
-              to: (a.to.0, N - 1 - a.to.1),
-              ..a
-            })
-        })
-        .collect(),
-      Direction::Up => (0..N)
-        .flat_map(|j| {
-          let col = self.0.each_mut().map(|row| &mut row[j]);
-          Self::shift_nums_left(col, j)
-            .into_iter()
-            .map(|a| TileAction {
-              from: (a.from.1, a.from.0),
-              to: (a.to.1, a.to.0),
-              ..a
-            })
-        })
-        .collect(),
-      Direction::Down => (0..N)
-        .flat_map(|j| {
-          let mut col = self.0.each_mut().map(|row| &mut row[j]);
-          col.reverse();
-          Self::shift_nums_left(col, j)
-            .into_iter()
-            .map(|a| TileAction {
-              from: (N - 1 - a.from.1, a.from.0),
-              to: (N - 1 - a.to.1, a.to.0),
-              ..a
-            })
-        })
-        .collect(),
+  fn trim_history(&mut self) {
+    if let Some(depth) = self.history_depth {
+      let excess = self.history.len().saturating_sub(depth);
+      self.history.drain(..excess);
     }
   }
+}
+
+/// Decides which tiles merge when shifted into each other.
+pub trait MergeRule {
+  /// Returns the value a tile with `moved` value leaves when it's shifted
+  /// into a tile with `target` value, or [`None`] if they don't merge. Both
+  /// values are non-zero exponents.
+  fn merge(&self, target: u8, moved: u8) -> Option<u8>;
+}
+
+/// The classic 2048 rule: equal tiles merge into a tile of twice the value.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ClassicMerge;
+
+impl MergeRule for ClassicMerge {
+  fn merge(&self, target: u8, moved: u8) -> Option<u8> {
+    (target == moved).then(|| target.saturating_add(1))
+  }
+}
 
-  /// In the given array of references to values, shifts values to the right
-  /// by 2048 rules.
-  fn shift_nums_left(row: [&mut u8; N], row_idx: usize) -> Vec<TileAction> {
-    let mut actions = Vec::new();
-    let mut i = 0;
-    for j in 1..N {
-      if *row[j] != 0 {
-        if *row[i] == 0 {
-          actions.push(TileAction {
-            kind: TileActionKind::Move,
-            value: *row[j],
-            from: (row_idx, j),
-            to: (row_idx, i),
-          });
-          *row[i] = *row[j];
-          *row[j] = 0;
-        } else if *row[j] == *row[i] {
-          *row[i] = row[i].saturating_add(1);
-          actions.push(TileAction {
-            kind: TileActionKind::Merge,
-            value: *row[i],
-            from: (row_idx, j),
-            to: (row_idx, i),
-          });
-          *row[j] = 0;
-          i += 1;
-        } else {
-          i += 1;
-          if i != j {
-            actions.push(TileAction {
-              kind: TileActionKind::Move,
-              value: *row[j],
-              from: (row_idx, j),
-              to: (row_idx, i),
-            });
-            *row[i] = *row[j];
-            *row[j] = 0;
-          }
-        }
-      }
+/// A [`TileAction`] within a single lane, with positions given as indices
+/// into the lane.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct LaneAction {
+  pub kind: TileActionKind,
+  pub moved_value: u8,
+  pub result_value: u8,
+  pub from: usize,
+  pub to: usize,
+}
+
+impl LaneAction {
+  /// Maps the action onto a board, given where lane indices lie on it.
+  fn on_board(self, cell: impl Fn(usize) -> (usize, usize)) -> TileAction {
+    TileAction::new(
+      self.kind,
+      self.moved_value,
+      self.result_value,
+      cell(self.from),
+      cell(self.to),
+    )
+  }
+}
+
+/// Shifts the values in `cells` toward its start by 2048 rules, merging tiles
+/// as `rule` says, each tile at most once. Rows and columns of a [`Board`] are
+/// shifted as such lanes. Returns the actions taken, in lane order.
+pub fn shift_lane(cells: &mut [u8], rule: &impl MergeRule) -> Vec<LaneAction> {
+  let mut actions = Vec::new();
+  let mut i = 0;
+  for j in 1..cells.len() {
+    let value = cells[j];
+    if value == 0 {
+      continue;
     }
-    actions
+    let (kind, result_value, to) = if cells[i] == 0 {
+      (TileActionKind::Move, value, i)
+    } else if let Some(merged) = rule.merge(cells[i], value) {
+      i += 1;
+      (TileActionKind::Merge, merged, i - 1)
+    } else {
+      i += 1;
+      if i == j {
+        continue;
+      }
+      (TileActionKind::Move, value, i)
+    };
+    cells[to] = result_value;
+    cells[j] = 0;
+    actions.push(LaneAction {
+      kind,
+      moved_value: value,
+      result_value,
+      from: j,
+      to,
+    });
   }
+  actions
 }
 
+/// A tile travelling from one cell to another during a shift, either moving
+/// into an empty cell or merging into an equal tile. `from` and `to` always
+/// differ and lie on the same row or column.
+///
+/// With the `serde` feature, deserializing checks these invariants and fails
+/// on an action the constructors wouldn't make.
 #[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(
+  feature = "serde",
+  derive(serde::Serialize, serde::Deserialize),
+  serde(try_from = "RawTileAction")
+)]
 pub struct TileAction {
   pub kind: TileActionKind,
-  pub value: u8,
+  /// The value of the travelling tile.
+  pub moved_value: u8,
+  /// The value left at `to` once the action is done. Same as `moved_value`
+  /// for moves.
+  pub result_value: u8,
   pub from: (usize, usize),
   pub to: (usize, usize),
 }
 
+impl TileAction {
+  /// Creates an action of a tile with `value` moving into an empty cell.
+  ///
+  /// Panics in debug builds if `from` and `to` are equal or don't lie on the
+  /// same row or column.
+  pub fn move_action(
+    value: u8,
+    from: (usize, usize),
+    to: (usize, usize),
+  ) -> Self {
+    Self::new(TileActionKind::Move, value, value, from, to)
+  }
+
+  /// Creates an action of a tile with `value` merging into an equal one.
+  ///
+  /// Panics in debug builds if `from` and `to` are equal or don't lie on the
+  /// same row or column.
+  pub fn merge_action(
+    value: u8,
+    from: (usize, usize),
+    to: (usize, usize),
+  ) -> Self {
+    Self::new(
+      TileActionKind::Merge,
+      value,
+      value.saturating_add(1),
+      from,
+      to,
+    )
+  }
+
+  /// Returns the points the action scores: the value of the tile a merge
+  /// makes, or nothing for a move.
+  pub fn points(&self) -> u32 {
+    match self.kind {
+      TileActionKind::Move => 0,
+      TileActionKind::Merge => 1u32
+        .checked_shl(self.result_value.into())
+        .unwrap_or(u32::MAX),
+    }
+  }
+
+  fn new(
+    kind: TileActionKind,
+    moved_value: u8,
+    result_value: u8,
+    from: (usize, usize),
+    to: (usize, usize),
+  ) -> Self {
+    debug_assert_ne!(from, to, "tile action must change the tile's position");
+    debug_assert!(
+      from.0 == to.0 || from.1 == to.1,
+      "tile action must stay on the same row or column"
+    );
+    Self {
+      kind,
+      moved_value,
+      result_value,
+      from,
+      to,
+    }
+  }
+}
+
+/// The serialized form of a [`TileAction`], checked before it becomes one.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawTileAction {
+  kind: TileActionKind,
+  moved_value: u8,
+  result_value: u8,
+  from: (usize, usize),
+  to: (usize, usize),
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawTileAction> for TileAction {
+  type Error = &'static str;
+
+  fn try_from(raw: RawTileAction) -> Result<Self, Self::Error> {
+    let RawTileAction {
+      kind,
+      moved_value,
+      result_value,
+      from,
+      to,
+    } = raw;
+    if from == to {
+      return Err("tile action must change the tile's position");
+    }
+    if from.0 != to.0 && from.1 != to.1 {
+      return Err("tile action must stay on the same row or column");
+    }
+    if kind == TileActionKind::Move && result_value != moved_value {
+      return Err("tile move must keep the tile's value");
+    }
+    Ok(Self::new(kind, moved_value, result_value, from, to))
+  }
+}
+
 impl std::fmt::Debug for TileAction {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(
-      f,
-      "{:?} {}: {:?} -> {:?}",
-      self.kind, self.value, self.from, self.to
-    )
+    write!(f, "{:?} {}", self.kind, self.moved_value)?;
+    if self.kind == TileActionKind::Merge {
+      write!(f, " into {}", self.result_value)?;
+    }
+    write!(f, ": {:?} -> {:?}", self.from, self.to)
   }
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TileActionKind {
   Move,
   Merge,
@@ -217,20 +1485,10 @@ mod tests {
   use super::*;
 
   fn moved(value: u8, from: (usize, usize), to: (usize, usize)) -> TileAction {
-    TileAction {
-      kind: TileActionKind::Move,
-      value,
-      from,
-      to,
-    }
+    TileAction::move_action(value, from, to)
   }
   fn merged(value: u8, from: (usize, usize), to: (usize, usize)) -> TileAction {
-    TileAction {
-      kind: TileActionKind::Merge,
-      value,
-      from,
-      to,
-    }
+    TileAction::merge_action(value, from, to)
   }
 
   #[test]
@@ -238,41 +1496,612 @@ mod tests {
     const SIZE: usize = 4;
     let board = Board::<SIZE>::empty();
     assert_eq!(board.0, [[0; SIZE]; SIZE]);
-    assert_eq!(board.size(), SIZE);
+    assert_eq!(board.size(), (SIZE, SIZE));
+    let board = Board::<4, 6>::empty();
+    assert_eq!(board.0, [[0; 6]; 4]);
+    assert_eq!(board.size(), (4, 6));
+  }
+
+  #[test]
+  fn new() {
+    const SIZE: usize = 4;
+    let board = Board::<SIZE>::new();
+    assert_eq!(board.iter_numbers().filter(|n| *n != 0).count(), 2);
+    assert_eq!(board.size(), (SIZE, SIZE));
+  }
+
+  #[test]
+  fn new_with_tiles() {
+    let count = |b: &Board<4>| b.iter_numbers().filter(|n| *n != 0).count();
+    assert_eq!(count(&Board::new_with_tiles(1)), 1);
+    assert_eq!(count(&Board::new_with_tiles(0)), 0);
+    assert_eq!(count(&Board::new_with_tiles(5)), 5);
+    assert_eq!(count(&Board::new_with_tiles(100)), 16);
+  }
+
+  #[test]
+  fn from_code() {
+    assert_eq!(
+      Board::<4>::from_code("0012001000a0000v"),
+      Some(Board([
+        [0, 0, 1, 2], //
+        [0, 0, 1, 0],
+        [0, 0, 10, 0],
+        [0, 0, 0, 31],
+      ]))
+    );
+    assert_eq!(Board::<4>::from_code("w000000000000000"), None);
+    assert_eq!(Board::<4>::from_code("0012001000a0000z"), None);
+    assert_eq!(
+      Board::<2>::from_code(" 1A00\n"),
+      Some(Board([[1, 10], [0, 0]]))
+    );
+    for code in ["", "000", "00000", "00-0", "0 00"] {
+      assert_eq!(Board::<2>::from_code(code), None, "{code:?}");
+    }
+  }
+
+  #[test]
+  fn from_rows() {
+    let rows = [
+      [0, 0, 1, 2], //
+      [0, 31, 1, 0],
+      [0, 0, 10, 0],
+    ];
+    let board = Board::<3, 4>::from_rows(rows).unwrap();
+    assert_eq!(board.iter_numbers().collect::<Vec<_>>(), rows.concat());
+    assert_eq!(Board::from_rows_unchecked(rows), board);
+
+    let err = Board::from_rows([[0, 1], [32, 40]]).unwrap_err();
+    assert_eq!(err, BoardError::ExponentTooLarge((1, 0)));
+    assert_eq!(err.to_string(), "exponent at (1, 0) is above 31");
+    assert_eq!(Board::from_rows_unchecked([[0, 1], [32, 40]]).get(1, 1), 40);
+  }
+
+  #[test]
+  fn with_layout() {
+    let count = |b: &Board<4>| b.iter_numbers().filter(|n| *n != 0).count();
+    let board = Board::<4>::with_layout(&InitialLayout::Standard).unwrap();
+    assert_eq!(count(&board), 2);
+    for n in [0, 1, 4, 16] {
+      let board = Board::<4>::with_layout(&InitialLayout::Random(n)).unwrap();
+      assert_eq!(count(&board), n);
+    }
+    let board = Board::<4>::with_layout(&InitialLayout::Fixed(vec![
+      ((0, 0), 6),
+      ((3, 1), 1),
+      ((2, 3), 1),
+    ]))
+    .unwrap();
+    assert_eq!(
+      board,
+      Board([
+        [6, 0, 0, 0], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 1],
+        [0, 1, 0, 0],
+      ])
+    );
+
+    for (layout, err) in [
+      (InitialLayout::Random(17), LayoutError::TooManyTiles(17)),
+      (
+        InitialLayout::Fixed(vec![((0, 0), 1), ((4, 0), 1)]),
+        LayoutError::OutOfBounds((4, 0)),
+      ),
+      (
+        InitialLayout::Fixed(vec![((1, 2), 1), ((1, 2), 2)]),
+        LayoutError::Overlapping((1, 2)),
+      ),
+      (
+        InitialLayout::Fixed(vec![((1, 2), 0)]),
+        LayoutError::EmptyTile((1, 2)),
+      ),
+    ] {
+      assert_eq!(Board::<4>::with_layout(&layout), Err(err));
+    }
+  }
+
+  #[test]
+  fn get_and_set_number() {
+    let mut board = Board::<4>::empty();
+    assert_eq!(board.get(1, 3), 0);
+    board.set(1, 3, 255);
+    assert_eq!(board.get(1, 3), 255);
+  }
+
+  #[test]
+  fn occupancy() {
+    let board = Board([
+      [1, 0, 2], //
+      [0, 0, 3],
+    ]);
+    let mask = board.occupancy();
+    assert_eq!(mask.count_empty(), 3);
+    assert_eq!(mask.count_occupied(), 3);
+    assert_eq!(mask.iter_empty_indices().collect::<Vec<_>>(), vec![1, 3, 4]);
+    assert_eq!(mask.nth_empty(0), Some(1));
+    assert_eq!(mask.nth_empty(2), Some(4));
+    assert_eq!(mask.nth_empty(3), None);
+    assert!(mask.is_empty(1, 1));
+    assert!(!mask.is_empty(1, 2));
+    let complement = mask.complement();
+    assert_eq!(complement.count_empty(), 3);
+    assert_eq!(mask.union(complement).count_empty(), 0);
+    assert_eq!(mask.intersection(complement).count_empty(), 6);
+    assert_eq!(Board::<8>::empty().occupancy().count_empty(), 64);
+    assert_eq!(Board::<8>::fill_distinct(1).occupancy().count_empty(), 0);
+  }
+
+  #[test]
+  fn occupancy_matches_scan() {
+    fn check<const R: usize, const C: usize>(board: &Board<R, C>) {
+      let mask = board.occupancy();
+      let empty = (0..R * C)
+        .filter(|&i| board.get(i / C, i % C) == 0)
+        .collect::<Vec<_>>();
+      assert_eq!(mask.iter_empty_indices().collect::<Vec<_>>(), empty);
+      assert_eq!(mask.count_empty(), empty.len());
+      for i in 0..R * C {
+        assert_eq!(mask.is_empty(i / C, i % C), empty.contains(&i));
+      }
+    }
+
+    let mut rng = rand::rng();
+    for _ in 0..20 {
+      let mut board = Board::<4, 6>::empty();
+      let mut square = Board::<8>::empty();
+      for _ in 0..200 {
+        match rng.random_range(0..3) {
+          0 => {
+            let (row, col) = (rng.random_range(0..4), rng.random_range(0..6));
+            board.set(row, col, rng.random_range(0..4));
+          }
+          1 => {
+            board.shift(*Direction::ALL.choose(&mut rng).unwrap());
+            square.shift(*Direction::ALL.choose(&mut rng).unwrap());
+          }
+          _ => {
+            board.spawn();
+            square.spawn();
+          }
+        }
+        check(&board);
+        check(&square);
+      }
+    }
+  }
+
+  #[test]
+  fn iter_cells() {
+    let board = Board([
+      [1, 0, 2], //
+      [0, 3, 0],
+    ]);
+    assert_eq!(
+      board.iter_cells().collect::<Vec<_>>(),
+      vec![
+        ((0, 0), 1),
+        ((0, 1), 0),
+        ((0, 2), 2),
+        ((1, 0), 0),
+        ((1, 1), 3),
+        ((1, 2), 0),
+      ]
+    );
+    assert_eq!(
+      board.iter_nonempty_cells().collect::<Vec<_>>(),
+      board
+        .iter_cells()
+        .filter(|(_, n)| *n != 0)
+        .collect::<Vec<_>>()
+    );
+    let board = Board::<4, 5>::fill_distinct(3);
+    for ((row, col), n) in board.iter_cells() {
+      assert_eq!(board.get(row, col), n);
+    }
+    assert_eq!(Board::<4>::empty().iter_nonempty_cells().count(), 0);
+  }
+
+  #[test]
+  fn count_empty() {
+    let empty = Board::<4>::empty();
+    assert_eq!(empty.count_empty(), 16);
+    assert!(!empty.is_full());
+    let partial = Board([
+      [1, 0, 2], //
+      [0, 3, 0],
+    ]);
+    assert_eq!(partial.count_empty(), 3);
+    assert!(!partial.is_full());
+    let mut full = Board::<3, 5>::fill_distinct(1);
+    assert_eq!(full.count_empty(), 0);
+    assert!(full.is_full());
+    assert_eq!(full.spawn(), None);
+  }
+
+  #[test]
+  fn count_value() {
+    let board = Board([
+      [1, 0, 2, 1], //
+      [0, 3, 1, 0],
+      [2, 0, 0, 0],
+      [1, 0, 0, 5],
+    ]);
+    assert_eq!(board.count_value(1), 4);
+    assert_eq!(board.count_value(2), 2);
+    assert_eq!(board.count_value(0), 8);
+    assert_eq!(board.count_value(4), 0);
+  }
+
+  #[test]
+  fn has_reached() {
+    let board = Board([
+      [1, 0, 2, 1], //
+      [0, 3, 1, 0],
+      [2, 0, 0, 0],
+      [1, 0, 0, 11],
+    ]);
+    assert!(board.has_reached(11));
+    assert!(board.has_reached(3));
+    assert!(!board.has_reached(12));
+    assert!(!Board::<4>::empty().has_reached(0));
+  }
+
+  #[test]
+  fn max_tile_and_win() {
+    let mut board = Board([
+      [0, 0, 0, 0], //
+      [0, 10, 10, 2],
+      [0, 0, 9, 0],
+      [0, 0, 0, 0],
+    ]);
+    assert_eq!(board.max_tile(), 10);
+    assert!(!board.is_won_default());
+    assert!(board.is_won(10));
+    board.shift(Direction::Right);
+    assert_eq!(board.get(1, 2), 11);
+    assert_eq!(board.max_tile(), 11);
+    assert!(board.is_won_default());
+    assert!(!board.is_won(12));
+
+    let empty = Board::<4>::empty();
+    assert_eq!(empty.max_tile(), 0);
+    assert!(!empty.is_won_default());
+    assert!(!empty.is_won(0));
+  }
+
+  #[test]
+  fn difficulty_estimate() {
+    let open = Board([
+      [0, 0, 0, 0], //
+      [0, 1, 0, 0],
+      [0, 0, 0, 0],
+      [0, 0, 1, 0],
+    ]);
+    let nearly_stuck = Board([
+      [1, 2, 3, 4], //
+      [5, 6, 7, 8],
+      [9, 10, 11, 12],
+      [13, 14, 15, 15],
+    ]);
+    let stuck = Board::<4>::fill_distinct(1);
+    assert_eq!(open.mergeable_pairs(), 0);
+    assert_eq!(nearly_stuck.mergeable_pairs(), 1);
+    assert_eq!(stuck.mergeable_pairs(), 0);
+    assert_eq!(Board::<4>::empty().difficulty_estimate(), 0.0);
+    assert_eq!(stuck.difficulty_estimate(), 1.0);
+    let (open, nearly_stuck) = (
+      open.difficulty_estimate(),
+      nearly_stuck.difficulty_estimate(),
+    );
+    assert!(open < 0.2, "{open}");
+    assert!(nearly_stuck > 0.8, "{nearly_stuck}");
+    assert!(nearly_stuck < 1.0, "{nearly_stuck}");
+  }
+
+  #[test]
+  fn neighbors() {
+    let board = Board([
+      [1, 2, 3], //
+      [4, 5, 6],
+      [7, 8, 9],
+    ]);
+    let neighbors = |row, col| board.neighbors(row, col).collect::<Vec<_>>();
+    assert_eq!(neighbors(0, 0), vec![((1, 0), 4), ((0, 1), 2)]);
+    assert_eq!(neighbors(2, 2), vec![((1, 2), 6), ((2, 1), 8)]);
+    assert_eq!(neighbors(0, 1), vec![((1, 1), 5), ((0, 0), 1), ((0, 2), 3)]);
+    assert_eq!(neighbors(1, 0), vec![((0, 0), 1), ((2, 0), 7), ((1, 1), 5)]);
+    assert_eq!(
+      neighbors(1, 1),
+      vec![((0, 1), 2), ((2, 1), 8), ((1, 0), 4), ((1, 2), 6)]
+    );
+    assert_eq!(Board::<1, 1>::empty().neighbors(0, 0).count(), 0);
+  }
+
+  #[test]
+  fn add_number() {
+    let mut board = Board::<4>::empty();
+    board.spawn();
+    assert_eq!(board.iter_numbers().filter(|n| *n != 0).count(), 1);
+    for _ in 0..15 {
+      assert!(board.spawn().is_some());
+    }
+    assert_eq!(board.iter_numbers().filter(|n| *n == 0).count(), 0);
+    assert!(board.spawn().is_none());
+  }
+
+  #[test]
+  fn spawn_weights() {
+    assert_eq!(
+      SpawnWeights::new([[1.0, -0.5], [0.0, 1.0]]),
+      Err(SpawnWeightsError::Negative((0, 1)))
+    );
+    assert_eq!(
+      SpawnWeights::new([[1.0, 1.0], [f32::NAN, 1.0]]),
+      Err(SpawnWeightsError::NotFinite((1, 0)))
+    );
+    assert!(SpawnWeights::new([[0.0; 3]; 2]).is_ok());
+    assert_eq!(
+      SpawnWeights::new([[f32::MAX, f32::MAX], [0.0, 1.0]]),
+      Err(SpawnWeightsError::TotalNotFinite)
+    );
+    let largest = SpawnWeights::new([[f32::MAX, 0.0], [0.0, 0.0]]).unwrap();
+    assert_eq!(
+      Board::<2>::empty().spawn_weighted(&largest).unwrap().1,
+      (0, 0)
+    );
+
+    let weights = SpawnWeights::<4>::edges_favored();
+    assert_eq!(weights.get(0, 2), SpawnWeights::<4>::EDGE_WEIGHT);
+    assert_eq!(weights.get(2, 1), 1.0);
+    const SPAWNS: usize = 10_000;
+    let on_edges = (0..SPAWNS)
+      .filter(|_| {
+        let (_, at) = Board::<4>::empty().spawn_weighted(&weights).unwrap();
+        SpawnPlacement::EdgesOnly.allows(at, (4, 4))
+      })
+      .count();
+    // 12 edge cells weighing 4 each against 4 center cells weighing 1
+    let share = on_edges as f64 / SPAWNS as f64;
+    assert!((0.9..0.945).contains(&share), "{share}");
+  }
+
+  #[test]
+  fn spawn_weighted_zero_weights() {
+    let weights = SpawnWeights::new([
+      [1.0, 0.0, 0.0], //
+      [0.0, 0.0, 0.0],
+    ])
+    .unwrap();
+    let mut board = Board::<2, 3>::empty();
+    for _ in 0..100 {
+      let (_, at) = board.clone().spawn_weighted(&weights).unwrap();
+      assert_eq!(at, (0, 0));
+    }
+    // only zero weighted cells left, so any of them goes
+    board.set(0, 0, 5);
+    for _ in 0..5 {
+      let (_, at) = board.spawn_weighted(&weights).unwrap();
+      assert_ne!(at, (0, 0));
+    }
+    assert_eq!(board.spawn_weighted(&weights), None);
+  }
+
+  #[test]
+  fn spawn_placement() {
+    let board = Board([
+      [1, 1, 1, 1], //
+      [1, 0, 0, 1],
+      [0, 0, 0, 1],
+      [1, 1, 0, 1],
+    ]);
+    for _ in 0..100 {
+      let (_, at) =
+        board.clone().spawn_with(SpawnPlacement::EdgesOnly).unwrap();
+      assert!([(2, 0), (3, 2)].contains(&at), "spawned at {at:?}");
+      let (_, at) = board
+        .clone()
+        .spawn_with(SpawnPlacement::CenterOnly)
+        .unwrap();
+      assert!([(1, 1), (1, 2), (2, 1), (2, 2)].contains(&at), "{at:?}");
+      // all corners are taken
+      let (_, at) = board.clone().spawn_with(SpawnPlacement::Corners).unwrap();
+      assert_eq!(board.get(at.0, at.1), 0);
+    }
+    let edges_full = Board::<4>::fill_distinct(1);
+    let mut board = edges_full.clone();
+    board.set(1, 2, 0);
+    let (_, at) = board.spawn_with(SpawnPlacement::EdgesOnly).unwrap();
+    assert_eq!(at, (1, 2));
+    assert_eq!(edges_full.clone().spawn_with(SpawnPlacement::Uniform), None);
+    let mut board = Board::<4>::empty();
+    for _ in 0..4 {
+      board.spawn_with(SpawnPlacement::Corners);
+    }
+    for (row, col) in [(0, 0), (0, 3), (3, 0), (3, 3)] {
+      assert_ne!(board.get(row, col), 0);
+    }
+  }
+
+  #[test]
+  fn spawn_is_uniform() {
+    let board = Board([
+      [1, 0, 2, 0], //
+      [0, 3, 0, 1],
+      [1, 1, 0, 0],
+      [0, 2, 2, 1],
+    ]);
+    let empty = board.occupancy().iter_empty_indices().collect::<Vec<_>>();
+    const SPAWNS: usize = 16_000;
+    let mut counts = [0usize; 16];
+    for _ in 0..SPAWNS {
+      let (_, (row, col)) = board.clone().spawn().unwrap();
+      counts[row * 4 + col] += 1;
+    }
+    let expected = SPAWNS as f64 / empty.len() as f64;
+    let chi_squared = empty
+      .iter()
+      .map(|&idx| (counts[idx] as f64 - expected).powi(2) / expected)
+      .sum::<f64>();
+    assert_eq!(counts.iter().sum::<usize>(), SPAWNS);
+    assert!(
+      counts
+        .iter()
+        .enumerate()
+        .all(|(i, n)| *n == 0 || empty.contains(&i))
+    );
+    // 99.99th percentile for 6 degrees of freedom
+    assert!(chi_squared < 27.86, "{counts:?}: {chi_squared}");
+  }
+
+  #[test]
+  fn pick_empty_matches_choose() {
+    // the spawn before the mask: sampling the allowed empty cells, or all
+    // of them, with `choose`
+    fn choose<const R: usize, const C: usize>(
+      board: &Board<R, C>,
+      placement: SpawnPlacement,
+      rng: &mut impl Rng,
+    ) -> Option<usize> {
+      let empty = || {
+        board
+          .iter_numbers()
+          .enumerate()
+          .filter_map(|(idx, n)| (n == 0).then_some(idx))
+      };
+      empty()
+        .filter(|&idx| placement.allows((idx / C, idx % C), (R, C)))
+        .choose(rng)
+        .or_else(|| empty().choose(rng))
+    }
+
+    fn check<const R: usize, const C: usize>(
+      board: &Board<R, C>,
+      placement: SpawnPlacement,
+      critical: f64,
+    ) {
+      const SPAWNS: u64 = 20_000;
+      let mut picked = vec![0u64; R * C];
+      let mut chosen = vec![0u64; R * C];
+      for seed in 0..SPAWNS {
+        let mut rng = StdRng::seed_from_u64(seed);
+        picked[board.pick_empty(placement, &mut rng).unwrap()] += 1;
+        let mut rng = StdRng::seed_from_u64(SPAWNS + seed);
+        chosen[choose(board, placement, &mut rng).unwrap()] += 1;
+      }
+      let chi_squared = picked
+        .iter()
+        .zip(&chosen)
+        .filter(|(a, b)| **a + **b > 0)
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2) / (a + b) as f64)
+        .sum::<f64>();
+      assert!(
+        chi_squared < critical,
+        "{placement:?}: {picked:?} vs {chosen:?}: {chi_squared}"
+      );
+      for (idx, n) in picked.iter().enumerate() {
+        assert!(*n == 0 || board.get(idx / C, idx % C) == 0);
+      }
+    }
+
+    let board = Board([
+      [1, 0, 2, 0], //
+      [0, 3, 0, 1],
+      [1, 1, 0, 0],
+      [0, 2, 2, 1],
+    ]);
+    // 99.99th percentiles for 6 and 4 degrees of freedom: 7 empty cells, 5
+    // of them on an edge
+    check(&board, SpawnPlacement::Uniform, 27.86);
+    check(&board, SpawnPlacement::EdgesOnly, 23.51);
+    // too large for a mask; 3 of the empty cells are in the center
+    let mut board = Board::<9>::fill_distinct(1);
+    for (row, col) in [(0, 0), (0, 8), (4, 4), (8, 3), (2, 5), (6, 6), (8, 8)] {
+      board.set(row, col, 0);
+    }
+    check(&board, SpawnPlacement::Uniform, 27.86);
+    check(&board, SpawnPlacement::CenterOnly, 18.42);
+    // no center cell is empty, so any of the 3 empty cells goes
+    let mut board = Board::<4>::fill_distinct(1);
+    for (row, col) in [(0, 1), (2, 0), (3, 2)] {
+      board.set(row, col, 0);
+    }
+    check(&board, SpawnPlacement::CenterOnly, 18.42);
   }
 
   #[test]
-  fn new() {
-    const SIZE: usize = 4;
-    let board = Board::<SIZE>::new();
-    assert_eq!(board.iter_numbers().filter(|n| *n != 0).count(), 2);
-    assert_eq!(board.size(), SIZE);
-  }
+  fn all_spawns() {
+    let board = Board([
+      [0, 2, 3, 4], //
+      [5, 6, 7, 8],
+      [9, 10, 0, 12],
+      [13, 14, 15, 16],
+    ]);
+    let spawns = board.all_spawns().collect::<Vec<_>>();
+    assert_eq!(spawns.len(), 4);
+    for (row, col, num, chance) in [
+      (0, 0, 1, 0.45),
+      (0, 0, 2, 0.05),
+      (2, 2, 1, 0.45),
+      (2, 2, 2, 0.05),
+    ] {
+      let mut expected = board.clone();
+      expected.set(row, col, num);
+      let (_, p) = spawns
+        .iter()
+        .find(|(b, _)| *b == expected)
+        .unwrap_or_else(|| panic!("{expected:?} should be spawned"));
+      assert!((p - chance).abs() < 1e-9, "expected {chance}, got {p}");
+    }
+    let total = spawns.iter().map(|(_, p)| p).sum::<f64>();
+    assert!((total - 1.0).abs() < 1e-9);
 
-  #[test]
-  fn get_and_set_number() {
-    let mut board = Board::<4>::empty();
-    assert_eq!(board.get(1, 3), 0);
-    board.set(1, 3, 255);
-    assert_eq!(board.get(1, 3), 255);
+    let mut full = board.clone();
+    full.set(0, 0, 1);
+    full.set(2, 2, 1);
+    assert_eq!(full.all_spawns().count(), 0);
   }
 
   #[test]
-  fn add_number() {
-    let mut board = Board::<4>::empty();
-    board.spawn();
-    assert_eq!(board.iter_numbers().filter(|n| *n != 0).count(), 1);
-    for _ in 0..15 {
-      assert!(board.spawn().is_some());
-    }
-    assert_eq!(board.iter_numbers().filter(|n| *n == 0).count(), 0);
-    assert!(board.spawn().is_none());
+  fn hash_and_ord() {
+    use std::collections::HashSet;
+
+    let board = Board([
+      [1, 0, 0, 0], //
+      [0, 0, 0, 0],
+      [2, 0, 0, 0],
+      [0, 0, 0, 0],
+    ]);
+    let mut seen = HashSet::new();
+    assert!(seen.insert(board.clone()));
+    let mut shifted = board.clone();
+    shifted.shift(Direction::Right);
+    assert!(seen.insert(shifted.clone()));
+    shifted.shift(Direction::Left);
+    assert!(!seen.insert(shifted), "loop back to the first board");
+
+    let mut ordered = [
+      Board([[0, 1], [0, 0]]),
+      Board([[1, 0], [0, 0]]),
+      Board([[0, 0], [0, 2]]),
+      Board([[0, 0], [1, 0]]),
+    ];
+    ordered.sort();
+    assert_eq!(
+      ordered,
+      [
+        Board([[0, 0], [0, 2]]),
+        Board([[0, 0], [1, 0]]),
+        Board([[0, 1], [0, 0]]),
+        Board([[1, 0], [0, 0]]),
+      ]
+    );
   }
 
   #[test]
   fn is_shiftable() {
     for board in [
-      Board::<4>::empty(),
       Board([
         [0, 2, 3, 4], //
         [5, 6, 7, 8],
@@ -330,13 +2159,314 @@ mod tests {
     ] {
       assert!(board.is_shiftable(), "{board:#?} should be shiftable");
     }
+    assert!(!Board::<4>::fill_distinct(1).is_shiftable());
+    assert!(!Board::<4>::empty().is_shiftable());
+  }
+
+  #[test]
+  fn fill_distinct() {
+    let board = Board::<4>::fill_distinct(1);
+    assert_eq!(
+      board,
+      Board([
+        [1, 2, 3, 4], //
+        [5, 6, 7, 8],
+        [9, 10, 11, 12],
+        [13, 14, 15, 16],
+      ])
+    );
+    assert_eq!(Board::<4>::fill_distinct(0), board);
+
+    let board = Board::<4>::fill_distinct(29);
+    assert!(board.iter_numbers().all(|n| n != 0));
+    assert_eq!(board.0[0], [29, 30, 31, 1]);
+    assert_eq!(board.0[1], [2, 3, 4, 5]);
+    assert!(!board.is_shiftable());
+    assert_eq!(Board::<4>::fill_distinct(255).0[0], [7, 8, 9, 10]);
+
+    fn check<const R: usize, const C: usize>() {
+      for start in 0..=u8::MAX {
+        let board = Board::<R, C>::fill_distinct(start);
+        assert_eq!(Board::from_rows(board.0), Ok(board.clone()), "{start}");
+        assert!(!board.is_shiftable(), "{start}");
+      }
+    }
+    check::<4, 4>();
+    check::<8, 8>();
+    check::<9, 9>();
+    check::<2, 8>();
+  }
+
+  #[test]
+  fn shiftability() {
+    use Direction::*;
+
     let board = Board([
       [1, 2, 3, 4], //
       [5, 6, 7, 8],
       [9, 10, 11, 12],
-      [13, 14, 15, 16],
+      [13, 14, 15, 0],
     ]);
-    assert!(!board.is_shiftable());
+    assert_eq!(board.legal_moves(), vec![Down, Right]);
+    let board = Board([
+      [1, 2, 3, 4], //
+      [5, 6, 7, 8],
+      [9, 10, 11, 12],
+      [13, 14, 15, 15],
+    ]);
+    assert_eq!(board.legal_moves(), vec![Left, Right]);
+    assert!(Board::<4>::empty().legal_moves().is_empty());
+  }
+
+  #[test]
+  fn shiftability_matches_shift() {
+    check_shiftability::<3, 3>();
+    check_shiftability::<2, 3>();
+    check_shiftability::<3, 2>();
+  }
+
+  fn check_shiftability<const R: usize, const C: usize>() {
+    for code in 0..3usize.pow((R * C) as u32) {
+      let mut board = Board::<R, C>::empty();
+      for idx in 0..R * C {
+        board.set(idx / C, idx % C, (code / 3usize.pow(idx as u32) % 3) as u8);
+      }
+      let shiftability = board.shiftability();
+      for dir in Direction::ALL {
+        let changed = !board.clone().shift(dir).is_empty();
+        assert_eq!(
+          shiftability.can(dir),
+          changed,
+          "{board:?} shifted to {dir:?}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn display() {
+    let board = Board([
+      [0, 1, 0], //
+      [11, 0, 3],
+    ]);
+    assert_eq!(board.to_string(), "   .    2    .\n2048    .    8\n");
+    assert_eq!(Board::<2>::empty().to_string(), ". .\n. .\n");
+    assert_eq!(Board([[1, 64]]).to_string(), "   2 2^64\n");
+  }
+
+  #[test]
+  fn from_str() {
+    let board = Board([
+      [0, 1, 0, 0], //
+      [11, 0, 3, 0],
+      [0, 0, 0, 0],
+      [0, 0, 31, 1],
+    ]);
+    let parsed = "
+      0    2 0 0
+      2048 0 8 0
+      0    0 0 0
+      0    0 2147483648 2
+    "
+    .parse::<Board<4>>();
+    assert_eq!(parsed, Ok(board.clone()));
+    assert_eq!(board.to_string().parse(), Ok(board));
+    assert_eq!("2 .\n\n. 4".parse(), Ok(Board([[1, 0], [0, 2]])));
+
+    for (text, err) in [
+      ("2 4", ParseBoardError::RowCount(1)),
+      ("2 4\n0 0\n0 0", ParseBoardError::RowCount(3)),
+      ("2 4\n0", ParseBoardError::ColumnCount(1)),
+      ("2 4 8\n0 0", ParseBoardError::ColumnCount(0)),
+      ("2 4\n0 x", ParseBoardError::NotANumber((1, 1))),
+      ("2 -4\n0 0", ParseBoardError::NotANumber((0, 1))),
+      ("2 4\n6 0", ParseBoardError::NotATile((1, 0))),
+      ("1 4\n0 0", ParseBoardError::NotATile((0, 0))),
+      ("2 4\n0 4294967296", ParseBoardError::NotANumber((1, 1))),
+    ] {
+      assert_eq!(text.parse::<Board<2>>(), Err(err), "{text:?}");
+    }
+    assert_eq!(
+      ParseBoardError::NotATile((1, 0)).to_string(),
+      "value at (1, 0) is not a power of two a tile can hold"
+    );
+  }
+
+  #[test]
+  fn max_in_corner() {
+    for (row, col) in [(0, 0), (0, 3), (3, 0), (3, 3)] {
+      let mut board = Board::<4>::empty();
+      board.set(1, 1, 3);
+      assert!(!board.max_in_corner());
+      board.set(row, col, 4);
+      assert!(board.max_in_corner(), "({row}, {col})");
+      board.set(row, col, 2);
+      assert!(!board.max_in_corner(), "({row}, {col})");
+    }
+    assert!(!Board::<4>::empty().max_in_corner());
+    // ties count as long as one of the largest tiles is in a corner
+    assert!(Board([[2, 0, 1], [0, 2, 0]]).max_in_corner());
+  }
+
+  #[test]
+  fn spawn_on_large_board() {
+    // too many cells for an occupancy mask
+    let mut board = Board::<9>::new();
+    assert_eq!(board.count_empty(), 79);
+    assert!(board.spawn_with(SpawnPlacement::Corners).is_some());
+    assert!(
+      board
+        .spawn_weighted(&SpawnWeights::edges_favored())
+        .is_some()
+    );
+    let spawned = Board::<9>::empty()
+      .spawn_evaluated(SpawnBias::Assisted, |b| b.get(4, 4) as f32);
+    assert_eq!(spawned, Some((2, (4, 4))));
+    while board.spawn().is_some() {}
+    assert!(board.is_full());
+    assert_eq!(Board::<12, 7>::new_seeded(1).count_empty(), 82);
+  }
+
+  #[test]
+  fn seeded_spawns() {
+    use rand::rngs::StdRng;
+
+    let play = |seed| {
+      let mut rng = StdRng::seed_from_u64(seed);
+      let mut board = Board::<4>::new_with_rng(&mut rng);
+      let mut boards = vec![board.clone()];
+      for dir in Direction::ALL.into_iter().cycle().take(40) {
+        if !board.shift(dir).is_empty() {
+          board.spawn_with_rng(&mut rng);
+        }
+        boards.push(board.clone());
+      }
+      boards
+    };
+    assert_eq!(play(7), play(7));
+    assert_ne!(play(7), play(8));
+
+    assert_eq!(Board::<4>::new_seeded(3), Board::<4>::new_seeded(3));
+    let boards = (0..20).map(Board::<4>::new_seeded).collect::<Vec<_>>();
+    assert!(boards.iter().any(|board| *board != boards[0]));
+    assert!(boards.iter().all(|board| board.count_value(0) == 14));
+  }
+
+  #[test]
+  fn spawn_evaluated() {
+    let board = Board([
+      [1, 0, 0], //
+      [0, 3, 0],
+    ]);
+    // prefers a big tile right of the 3
+    let evaluate = |board: &Board<2, 3>| board.get(1, 2) as f32;
+    let mut assisted = board.clone();
+    assert_eq!(
+      assisted.spawn_evaluated(SpawnBias::Assisted, evaluate),
+      Some((2, (1, 2)))
+    );
+    assert_eq!(assisted.get(1, 2), 2);
+    // every other spawn rates the same, so the first one is taken
+    let mut adversarial = board.clone();
+    assert_eq!(
+      adversarial.spawn_evaluated(SpawnBias::Adversarial, evaluate),
+      Some((1, (0, 1)))
+    );
+    assert_eq!(adversarial.occupancy().count_occupied(), 3);
+
+    let mut full = Board::<2>::fill_distinct(1);
+    assert_eq!(full.spawn_evaluated(SpawnBias::Assisted, |_| 0.0), None);
+  }
+
+  #[test]
+  fn best_reply() {
+    // rates empty cells and, less, small tiles
+    let evaluate = |board: &Board<2, 3>| {
+      board
+        .iter_numbers()
+        .map(|n| if n == 0 { 10.0 } else { -(n as f32) })
+        .sum()
+    };
+    let board = Board([
+      [1, 1, 0], //
+      [2, 0, 3],
+    ]);
+    // a 4 right of the 1s can only be replied to by going left
+    let mut evil = board.clone();
+    let rate = |board: &Board<2, 3>| board.best_reply(1, &evaluate);
+    assert_eq!(
+      evil.spawn_evaluated(SpawnBias::Adversarial, rate),
+      Some((2, (0, 2)))
+    );
+    assert_eq!(rate(&evil), 11.0);
+    let mut kind = board.clone();
+    assert_eq!(
+      kind.spawn_evaluated(SpawnBias::Assisted, rate),
+      Some((2, (1, 1)))
+    );
+    for _ in 0..3 {
+      assert_eq!(
+        board.clone().spawn_evaluated(SpawnBias::Adversarial, rate),
+        Some((2, (0, 2)))
+      );
+    }
+
+    assert_eq!(
+      Board::<2, 3>::fill_distinct(1).best_reply(2, &evaluate),
+      f32::NEG_INFINITY
+    );
+    assert!(board.best_reply(3, &evaluate).is_finite());
+  }
+
+  #[test]
+  fn peek_shift() {
+    let board = Board([
+      [1, 1, 2, 2], //
+      [0, 0, 0, 3],
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+    ]);
+    for dir in Direction::ALL {
+      let mut shifted = board.clone();
+      assert_eq!(board.peek_shift(dir), shifted.shift(dir), "{dir:?}");
+    }
+    let merges = board
+      .peek_shift(Direction::Left)
+      .into_iter()
+      .filter(|a| a.kind == TileActionKind::Merge)
+      .count();
+    assert_eq!(merges, 2);
+    assert_eq!(board.get(0, 0), 1);
+  }
+
+  #[test]
+  fn is_solved_snake() {
+    for board in [
+      Board([
+        [11, 10, 9, 8], //
+        [4, 5, 6, 7],
+        [3, 2, 1, 1],
+        [0, 0, 0, 0],
+      ]),
+      Board([
+        [0, 0, 5, 6], //
+        [0, 0, 4, 7],
+        [0, 1, 3, 8],
+        [0, 1, 2, 9],
+      ]),
+      Board::empty(),
+    ] {
+      assert_eq!(board.is_solved_snake(), 1.0, "{board:?}");
+    }
+    assert_eq!(Board([[5, 4, 3], [0, 1, 2]]).is_solved_snake(), 1.0);
+    assert_eq!(Board([[5, 0], [4, 1], [3, 2]]).is_solved_snake(), 1.0);
+    let board = Board([
+      [1, 8, 3, 10], //
+      [7, 2, 11, 4],
+      [9, 5, 1, 6],
+      [0, 3, 0, 2],
+    ]);
+    assert!(board.is_solved_snake() <= 0.25);
   }
 
   #[test]
@@ -368,7 +2498,7 @@ mod tests {
       ([1, 2, 1, 2], [1, 2, 1, 2]),
     ] {
       let mut shifted = before;
-      Board::<4>::shift_nums_left(shifted.each_mut(), 0);
+      shift_lane(&mut shifted, &ClassicMerge);
       assert_eq!(
         after, shifted,
         "expected {after:?}, got {shifted:?} (originally {before:?})"
@@ -376,6 +2506,411 @@ mod tests {
     }
   }
 
+  #[test]
+  fn shift_lanes_exhaustive() {
+    check_lanes::<3>();
+    check_lanes::<4>();
+    check_lanes::<6>();
+  }
+
+  fn check_lanes<const L: usize>() {
+    for code in 0..3usize.pow(L as u32) {
+      let before: [u8; L] =
+        std::array::from_fn(|i| (code / 3usize.pow(i as u32) % 3) as u8);
+      // slide the tiles together, merging each pair of equal ones once
+      let mut expected = [0; L];
+      let (mut len, mut can_merge) = (0, false);
+      for n in before.into_iter().filter(|n| *n != 0) {
+        if can_merge && expected[len - 1] == n {
+          expected[len - 1] += 1;
+          can_merge = false;
+        } else {
+          expected[len] = n;
+          len += 1;
+          can_merge = true;
+        }
+      }
+      let mut shifted = before;
+      let actions = shift_lane(&mut shifted, &ClassicMerge);
+      assert_eq!(shifted, expected, "originally {before:?}");
+      assert_eq!(actions.is_empty(), before == expected, "{before:?}");
+
+      // boards shift their rows as lanes
+      let mut board = Board([before]);
+      let board_actions = board.shift(Direction::Left);
+      assert_eq!(board, Board([expected]), "originally {before:?}");
+      assert_eq!(
+        board_actions,
+        actions
+          .iter()
+          .map(|a| a.on_board(|idx| (0, idx)))
+          .collect::<Vec<_>>()
+      );
+    }
+  }
+
+  #[test]
+  fn scored_board() {
+    use Direction::*;
+
+    let mut board = ScoredBoard::from(Board([
+      [1, 1, 2, 2], //
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+      [0, 0, 1, 1],
+    ]));
+    assert_eq!(board.score(), 0);
+    // 4 + 8 + 4
+    board.shift(Left);
+    assert_eq!(board.score(), 16);
+    assert_eq!(
+      *board.board(),
+      Board([
+        [2, 3, 0, 0], //
+        [0, 0, 0, 0],
+        [0, 0, 0, 0],
+        [2, 0, 0, 0],
+      ])
+    );
+    // 8, then 16
+    board.shift(Up);
+    assert_eq!(board.score(), 24);
+    board.shift(Right);
+    assert_eq!(board.score(), 40);
+    // nothing merges
+    board.shift(Down);
+    assert_eq!(board.score(), 40);
+    assert_eq!(board.shift(Down), vec![]);
+    assert_eq!(board.score(), 40);
+
+    let mut board = ScoredBoard::from(Board([[16, 16, 40, 40]]));
+    board.shift(Right);
+    assert_eq!(board.score(), u32::MAX);
+    assert_eq!(ScoredBoard::<4>::new().score(), 0);
+    assert_eq!(ScoredBoard::<4>::empty().board(), &Board::empty());
+  }
+
+  #[test]
+  fn undoable_board() {
+    use Direction::*;
+
+    let start = Board([
+      [1, 1, 0, 0], //
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+      [0, 0, 0, 2],
+    ]);
+    let mut board = UndoableBoard::from(start.clone());
+    assert!(!board.undo());
+    board.shift(Left);
+    let (_, (row, col)) = board.spawn().unwrap();
+    assert_ne!(board.board().get(row, col), 0);
+    assert!(board.undo());
+    assert_eq!(*board.board(), start);
+    assert!(!board.undo());
+
+    // shifts that change nothing leave nothing to undo
+    board.shift(Up);
+    assert_eq!(board.shift(Up), vec![]);
+    assert_eq!(board.history_len(), 1);
+    assert!(board.undo());
+    assert_eq!(*board.board(), start);
+
+    let mut board = UndoableBoard::from(Board([[1, 0, 0, 0]]));
+    for dir in [Right, Left].into_iter().cycle().take(10) {
+      board.shift(dir);
+    }
+    assert_eq!(
+      board.history_len(),
+      UndoableBoard::<1, 4>::DEFAULT_HISTORY_DEPTH
+    );
+    let mut board = board.with_history_depth(Some(3));
+    assert_eq!(board.history_len(), 3);
+    for _ in 0..3 {
+      assert!(board.undo());
+    }
+    assert!(!board.undo());
+    // the last shift was to the left
+    assert_eq!(*board.board(), Board([[0, 0, 0, 1]]));
+
+    let mut board =
+      UndoableBoard::from(Board([[1, 0, 0, 0]])).with_history_depth(None);
+    for dir in [Right, Left].into_iter().cycle().take(100) {
+      board.shift(dir);
+    }
+    assert_eq!(board.history_len(), 100);
+    let mut board = board.with_history_depth(Some(0));
+    board.shift(Right);
+    assert!(!board.undo());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn serde_round_trip() {
+    let board = Board([
+      [1, 0, 3, 11], //
+      [0, 2, 2, 0],
+      [5, 0, 0, 1],
+      [0, 0, 4, 0],
+    ]);
+    let json = serde_json::to_string(&board).unwrap();
+    assert_eq!(
+      json,
+      r#"{"rows":4,"cols":4,"cells":[1,0,3,11,0,2,2,0,5,0,0,1,0,0,4,0]}"#
+    );
+    assert_eq!(serde_json::from_str::<Board<4>>(&json).unwrap(), board);
+
+    let mut shifted = board.clone();
+    let actions = shifted.shift(Direction::Left);
+    let json = serde_json::to_string(&(Direction::Left, &actions)).unwrap();
+    let back: (Direction, Vec<TileAction>) =
+      serde_json::from_str(&json).unwrap();
+    assert_eq!(back, (Direction::Left, actions));
+
+    let board = Board::<5>::fill_distinct(1);
+    let json = serde_json::to_string(&board).unwrap();
+    assert_eq!(serde_json::from_str::<Board<5>>(&json).unwrap(), board);
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn serde_wrong_dimensions() {
+    let json = serde_json::to_string(&Board::<5>::fill_distinct(1)).unwrap();
+    let err = serde_json::from_str::<Board<4>>(&json).unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .starts_with("expected a 4x4 board, got a 5x5")
+    );
+    assert!(serde_json::from_str::<Board<5, 4>>(&json).is_err());
+    for json in [
+      r#"{"rows":2,"cols":2,"cells":[1,2,3]}"#,
+      r#"{"rows":2,"cols":2,"cells":[1,2,3,4,5]}"#,
+      r#"{"rows":2,"cols":2}"#,
+      r#"[1,2,3,4]"#,
+    ] {
+      assert!(serde_json::from_str::<Board<2>>(json).is_err(), "{json}");
+    }
+    let err = serde_json::from_str::<Board<2>>(
+      r#"{"rows":2,"cols":2,"cells":[1,2,32,4]}"#,
+    )
+    .unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .starts_with("exponent at (1, 0) is above 31")
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn serde_invalid_tile_action() {
+    let json = serde_json::to_string(&merged(2, (0, 3), (0, 0))).unwrap();
+    assert_eq!(
+      serde_json::from_str::<TileAction>(&json).unwrap(),
+      merged(2, (0, 3), (0, 0))
+    );
+    for (json, message) in [
+      (
+        r#"{"kind":"Move","moved_value":1,"result_value":1,"from":[1,1],"to":[1,1]}"#,
+        "tile action must change the tile's position",
+      ),
+      (
+        r#"{"kind":"Move","moved_value":1,"result_value":1,"from":[0,0],"to":[1,1]}"#,
+        "tile action must stay on the same row or column",
+      ),
+      (
+        r#"{"kind":"Move","moved_value":1,"result_value":2,"from":[0,0],"to":[0,1]}"#,
+        "tile move must keep the tile's value",
+      ),
+    ] {
+      let err = serde_json::from_str::<TileAction>(json).unwrap_err();
+      assert!(err.to_string().starts_with(message), "{json}");
+    }
+  }
+
+  #[test]
+  fn shift_lane_with_rule() {
+    // merges tiles that differ by one into the larger one plus one
+    struct Adjacent;
+
+    impl MergeRule for Adjacent {
+      fn merge(&self, target: u8, moved: u8) -> Option<u8> {
+        (target.abs_diff(moved) == 1).then(|| target.max(moved) + 1)
+      }
+    }
+
+    let mut lane = [1, 1, 0, 2, 3];
+    let actions = shift_lane(&mut lane, &Adjacent);
+    assert_eq!(lane, [1, 3, 3, 0, 0]);
+    assert_eq!(
+      actions,
+      vec![
+        LaneAction {
+          kind: TileActionKind::Merge,
+          moved_value: 2,
+          result_value: 3,
+          from: 3,
+          to: 1,
+        },
+        LaneAction {
+          kind: TileActionKind::Move,
+          moved_value: 3,
+          result_value: 3,
+          from: 4,
+          to: 2,
+        },
+      ]
+    );
+    let mut empty: [u8; 0] = [];
+    assert!(shift_lane(&mut empty, &ClassicMerge).is_empty());
+  }
+
+  #[test]
+  fn tile_action() {
+    let action = TileAction::merge_action(2, (1, 2), (1, 0));
+    assert_eq!(action.kind, TileActionKind::Merge);
+    assert_eq!(action.moved_value, 2);
+    assert_eq!(action.result_value, 3);
+    assert_eq!(action.from, (1, 2));
+    assert_eq!(action.to, (1, 0));
+    assert_eq!(format!("{action:?}"), "Merge 2 into 3: (1, 2) -> (1, 0)");
+    let action = TileAction::move_action(1, (3, 1), (0, 1));
+    assert_eq!(action.kind, TileActionKind::Move);
+    assert_eq!(action.moved_value, 1);
+    assert_eq!(action.result_value, 1);
+    assert_eq!(format!("{action:?}"), "Move 1: (3, 1) -> (0, 1)");
+    assert_eq!(
+      TileAction::merge_action(u8::MAX, (0, 0), (0, 1)).result_value,
+      u8::MAX
+    );
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "must change the tile's position")]
+  fn tile_action_in_place() {
+    TileAction::move_action(1, (2, 2), (2, 2));
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "must stay on the same row or column")]
+  fn tile_action_diagonal() {
+    TileAction::merge_action(2, (0, 0), (1, 1));
+  }
+
+  #[test]
+  fn shift_rectangular() {
+    use Direction::*;
+
+    let board = Board([
+      [1, 0, 1], //
+      [0, 2, 1],
+    ]);
+    for (dir, after, actions) in [
+      (
+        Up,
+        Board([[1, 2, 2], [0, 0, 0]]),
+        vec![moved(2, (1, 1), (0, 1)), merged(1, (1, 2), (0, 2))],
+      ),
+      (
+        Down,
+        Board([[0, 0, 0], [1, 2, 2]]),
+        vec![moved(1, (0, 0), (1, 0)), merged(1, (0, 2), (1, 2))],
+      ),
+      (
+        Left,
+        Board([[2, 0, 0], [2, 1, 0]]),
+        vec![
+          merged(1, (0, 2), (0, 0)),
+          moved(2, (1, 1), (1, 0)),
+          moved(1, (1, 2), (1, 1)),
+        ],
+      ),
+      (
+        Right,
+        Board([[0, 0, 2], [0, 2, 1]]),
+        vec![merged(1, (0, 0), (0, 2))],
+      ),
+    ] {
+      let mut shifted = board.clone();
+      assert_eq!(shifted.shift(dir), actions, "{dir:?}");
+      assert_eq!(shifted, after, "{dir:?}");
+    }
+  }
+
+  #[test]
+  fn shift_two_by_four() {
+    use Direction::*;
+
+    let board = Board::<2, 4>::from_rows([
+      [1, 1, 0, 2], //
+      [0, 1, 2, 2],
+    ])
+    .unwrap();
+    for (dir, after, actions) in [
+      (
+        Up,
+        Board([[1, 2, 2, 3], [0, 0, 0, 0]]),
+        vec![
+          merged(1, (1, 1), (0, 1)),
+          moved(2, (1, 2), (0, 2)),
+          merged(2, (1, 3), (0, 3)),
+        ],
+      ),
+      (
+        Down,
+        Board([[0, 0, 0, 0], [1, 2, 2, 3]]),
+        vec![
+          moved(1, (0, 0), (1, 0)),
+          merged(1, (0, 1), (1, 1)),
+          merged(2, (0, 3), (1, 3)),
+        ],
+      ),
+      (
+        Left,
+        Board([[2, 2, 0, 0], [1, 3, 0, 0]]),
+        vec![
+          merged(1, (0, 1), (0, 0)),
+          moved(2, (0, 3), (0, 1)),
+          moved(1, (1, 1), (1, 0)),
+          moved(2, (1, 2), (1, 1)),
+          merged(2, (1, 3), (1, 1)),
+        ],
+      ),
+      (
+        Right,
+        Board([[0, 0, 2, 2], [0, 0, 1, 3]]),
+        vec![
+          moved(1, (0, 1), (0, 2)),
+          merged(1, (0, 0), (0, 2)),
+          merged(2, (1, 2), (1, 3)),
+          moved(1, (1, 1), (1, 2)),
+        ],
+      ),
+    ] {
+      let mut shifted = board.clone();
+      assert_eq!(shifted.shift(dir), actions, "{dir:?}");
+      assert_eq!(shifted, after, "{dir:?}");
+      assert_eq!(shifted.size(), (2, 4));
+    }
+    assert_eq!(SquareBoard::<4>::empty(), Board::<4>::empty());
+  }
+
+  #[test]
+  fn play_rectangular() {
+    let mut board = Board::<2, 8>::new();
+    let mut moves = 0;
+    while let Some(dir) = board.legal_moves().first().copied() {
+      assert!(!board.shift(dir).is_empty());
+      assert!(board.spawn().is_some());
+      moves += 1;
+      assert!(moves < 100_000, "the game should end");
+    }
+    assert!(board.iter_numbers().all(|n| n != 0));
+  }
+
   #[test]
   fn shift_empty() {
     use Direction::*;
@@ -405,13 +2940,13 @@ mod tests {
         ]),
         vec![
           moved(2, (0, 3), (0, 1)),
-          merged(2, (1, 2), (1, 0)),
+          merged(1, (1, 2), (1, 0)),
           moved(2, (1, 3), (1, 1)),
           moved(2, (2, 2), (2, 1)),
-          merged(3, (2, 3), (2, 1)),
-          merged(2, (3, 1), (3, 0)),
+          merged(2, (2, 3), (2, 1)),
+          merged(1, (3, 1), (3, 0)),
           moved(2, (3, 2), (3, 1)),
-          merged(3, (3, 3), (3, 1)),
+          merged(2, (3, 3), (3, 1)),
         ],
       ),
       (
@@ -430,13 +2965,13 @@ mod tests {
         ]),
         vec![
           moved(2, (0, 0), (0, 2)),
-          merged(2, (1, 1), (1, 3)),
+          merged(1, (1, 1), (1, 3)),
           moved(2, (1, 0), (1, 2)),
           moved(2, (2, 1), (2, 2)),
-          merged(3, (2, 0), (2, 2)),
-          merged(2, (3, 2), (3, 3)),
+          merged(2, (2, 0), (2, 2)),
+          merged(1, (3, 2), (3, 3)),
           moved(2, (3, 1), (3, 2)),
-          merged(3, (3, 0), (3, 2)),
+          merged(2, (3, 0), (3, 2)),
         ],
       ),
       (
@@ -455,13 +2990,13 @@ mod tests {
         ]),
         vec![
           moved(2, (3, 0), (1, 0)),
-          merged(2, (2, 1), (0, 1)),
+          merged(1, (2, 1), (0, 1)),
           moved(2, (3, 1), (1, 1)),
           moved(2, (2, 2), (1, 2)),
-          merged(3, (3, 2), (1, 2)),
-          merged(2, (1, 3), (0, 3)),
+          merged(2, (3, 2), (1, 2)),
+          merged(1, (1, 3), (0, 3)),
           moved(2, (2, 3), (1, 3)),
-          merged(3, (3, 3), (1, 3)),
+          merged(2, (3, 3), (1, 3)),
         ],
       ),
       (
@@ -480,13 +3015,13 @@ mod tests {
         ]),
         vec![
           moved(2, (0, 0), (2, 0)),
-          merged(2, (1, 1), (3, 1)),
+          merged(1, (1, 1), (3, 1)),
           moved(2, (0, 1), (2, 1)),
           moved(2, (1, 2), (2, 2)),
-          merged(3, (0, 2), (2, 2)),
-          merged(2, (2, 3), (3, 3)),
+          merged(2, (0, 2), (2, 2)),
+          merged(1, (2, 3), (3, 3)),
           moved(2, (1, 3), (2, 3)),
-          merged(3, (0, 3), (2, 3)),
+          merged(2, (0, 3), (2, 3)),
         ],
       ),
     ] {
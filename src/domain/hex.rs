@@ -0,0 +1,362 @@
+//! An experimental hexagonal variant of the game, played on a hexagon of 19
+//! cells with six directions to shift to. Only the rules are here, there's no
+//! UI for it.
+
+use rand::prelude::*;
+
+use super::{ClassicMerge, TileActionKind, random_spawn_value, shift_lane};
+
+/// A cell of a [`HexBoard`] in axial coordinates: `q` grows to the east and
+/// `r` to the south-east, with the center cell at the origin.
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct Hex {
+  pub q: i8,
+  pub r: i8,
+}
+
+impl Hex {
+  pub const fn new(q: i8, r: i8) -> Self {
+    Self { q, r }
+  }
+
+  /// Returns the cell next to this one in `direction`.
+  pub fn neighbor(self, direction: HexDirection) -> Self {
+    let (dq, dr) = direction.offset();
+    Self::new(self.q + dq, self.r + dr)
+  }
+
+  fn on_board(self) -> bool {
+    let s = -self.q - self.r;
+    self.q.abs().max(self.r.abs()).max(s.abs()) <= HexBoard::RADIUS
+  }
+}
+
+/// The hexagonal grid shift direction.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum HexDirection {
+  East,
+  West,
+  NorthEast,
+  SouthWest,
+  NorthWest,
+  SouthEast,
+}
+
+impl HexDirection {
+  pub const ALL: [Self; 6] = [
+    Self::East,
+    Self::West,
+    Self::NorthEast,
+    Self::SouthWest,
+    Self::NorthWest,
+    Self::SouthEast,
+  ];
+
+  /// Returns the change of axial coordinates of a step in the direction.
+  fn offset(self) -> (i8, i8) {
+    match self {
+      Self::East => (1, 0),
+      Self::West => (-1, 0),
+      Self::NorthEast => (1, -1),
+      Self::SouthWest => (-1, 1),
+      Self::NorthWest => (0, -1),
+      Self::SouthEast => (0, 1),
+    }
+  }
+}
+
+/// A tile travelling from one cell of a [`HexBoard`] to another during a
+/// shift, like a [`TileAction`](super::TileAction) on a square board.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct HexAction {
+  pub kind: TileActionKind,
+  pub moved_value: u8,
+  pub result_value: u8,
+  pub from: Hex,
+  pub to: Hex,
+}
+
+/// A hexagon of cells with [`RADIUS`](Self::RADIUS) cells from the center
+/// to each side, holding exponents like a [`Board`](super::Board). Every
+/// shift is made of parallel lanes shifted with [`shift_lane`].
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct HexBoard([u8; Self::CELLS]);
+
+impl Default for HexBoard {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl HexBoard {
+  pub const RADIUS: i8 = 2;
+  pub const CELLS: usize = 19;
+
+  /// Returns an empty board.
+  pub fn empty() -> Self {
+    Self([0; Self::CELLS])
+  }
+
+  /// Returns a board with two randomly spawned tiles.
+  pub fn new() -> Self {
+    let mut board = Self::empty();
+    board.spawn();
+    board.spawn();
+    board
+  }
+
+  /// Returns every cell of the board, row by row from the north.
+  pub fn cells() -> impl Iterator<Item = Hex> {
+    (-Self::RADIUS..=Self::RADIUS).flat_map(|r| {
+      (-Self::RADIUS..=Self::RADIUS)
+        .map(move |q| Hex::new(q, r))
+        .filter(|hex| hex.on_board())
+    })
+  }
+
+  fn index(hex: Hex) -> Option<usize> {
+    Self::cells().position(|h| h == hex)
+  }
+
+  /// Returns the value at `hex`, or [`None`] if it's not on the board.
+  pub fn get(&self, hex: Hex) -> Option<u8> {
+    Self::index(hex).map(|idx| self.0[idx])
+  }
+
+  /// Sets the value at `hex`. Panics if it's not on the board.
+  pub fn set(&mut self, hex: Hex, value: u8) {
+    let idx = Self::index(hex).expect("cell out of the board");
+    self.0[idx] = value;
+  }
+
+  /// Returns the lanes shifting to `direction` is made of, each listing its
+  /// cells from the one farthest in `direction`.
+  fn lanes(direction: HexDirection) -> Vec<Vec<Hex>> {
+    let (dq, dr) = direction.offset();
+    let mut lanes = Vec::new();
+    // a lane starts on a cell whose neighbor in `direction` is off the board
+    for start in Self::cells().filter(|h| !h.neighbor(direction).on_board()) {
+      let lane = std::iter::successors(Some(start), |h| {
+        Some(Hex::new(h.q - dq, h.r - dr)).filter(|h| h.on_board())
+      });
+      lanes.push(lane.collect());
+    }
+    lanes
+  }
+
+  /// Moves values on the board to `direction` and returns the actions that
+  /// were taken to update it.
+  pub fn shift(&mut self, direction: HexDirection) -> Vec<HexAction> {
+    let mut actions = Vec::new();
+    for lane in Self::lanes(direction) {
+      let mut values = lane
+        .iter()
+        .map(|&hex| self.get(hex).unwrap_or(0))
+        .collect::<Vec<_>>();
+      let lane_actions = shift_lane(&mut values, &ClassicMerge);
+      for (&hex, value) in lane.iter().zip(values) {
+        self.set(hex, value);
+      }
+      actions.extend(lane_actions.into_iter().map(|a| HexAction {
+        kind: a.kind,
+        moved_value: a.moved_value,
+        result_value: a.result_value,
+        from: lane[a.from],
+        to: lane[a.to],
+      }));
+    }
+    actions
+  }
+
+  /// Returns `true` if the board can be shifted to any direction, that is,
+  /// if a tile has an empty or equal neighbor.
+  pub fn is_shiftable(&self) -> bool {
+    Self::cells().any(|hex| {
+      let value = self.get(hex).unwrap_or(0);
+      value != 0
+        && HexDirection::ALL.into_iter().any(|dir| {
+          self
+            .get(hex.neighbor(dir))
+            .is_some_and(|n| n == 0 || n == value)
+        })
+    })
+  }
+
+  /// Tries to add a 2 or 4 value to a random empty cell. Returns [`Some`]
+  /// spawned value and its cell on success, [`None`] if the board is full.
+  pub fn spawn(&mut self) -> Option<(u8, Hex)> {
+    let mut rng = rand::rng();
+    let hex = Self::cells()
+      .filter(|&hex| self.get(hex) == Some(0))
+      .choose(&mut rng)?;
+    let value = random_spawn_value(&mut rng);
+    self.set(hex, value);
+    Some((value, hex))
+  }
+}
+
+/// Draws the board as rows of exponents, with `.` for empty cells.
+impl std::fmt::Display for HexBoard {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for r in -Self::RADIUS..=Self::RADIUS {
+      let row = Self::cells()
+        .filter(|hex| hex.r == r)
+        .map(|hex| match self.get(hex).unwrap_or(0) {
+          0 => ".".to_string(),
+          n => n.to_string(),
+        })
+        .collect::<Vec<_>>();
+      let indent = " ".repeat(r.unsigned_abs() as usize);
+      writeln!(f, "{indent}{}", row.join(" "))?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cells() {
+    assert_eq!(HexBoard::cells().count(), HexBoard::CELLS);
+    assert!(HexBoard::cells().all(|hex| hex.on_board()));
+    assert_eq!(HexBoard::empty().get(Hex::new(2, -2)), Some(0));
+    assert_eq!(HexBoard::empty().get(Hex::new(2, 1)), None);
+  }
+
+  #[test]
+  fn lanes() {
+    for dir in HexDirection::ALL {
+      let lanes = HexBoard::lanes(dir);
+      let mut lengths = lanes.iter().map(|l| l.len()).collect::<Vec<_>>();
+      lengths.sort();
+      assert_eq!(lengths, vec![3, 3, 4, 4, 5], "{dir:?}");
+      let mut cells = lanes.iter().flatten().copied().collect::<Vec<_>>();
+      cells.sort_by_key(|hex| (hex.r, hex.q));
+      cells.dedup();
+      assert_eq!(cells.len(), HexBoard::CELLS, "{dir:?}");
+      for lane in &lanes {
+        for pair in lane.windows(2) {
+          assert_eq!(pair[1].neighbor(dir), pair[0], "{dir:?}");
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn shift_lanes_exhaustive() {
+    // every lane of every direction shifts like a plain lane
+    for dir in HexDirection::ALL {
+      for lane in HexBoard::lanes(dir) {
+        let len = lane.len();
+        for code in 0..3usize.pow(len as u32) {
+          let before = (0..len)
+            .map(|i| (code / 3usize.pow(i as u32) % 3) as u8)
+            .collect::<Vec<_>>();
+          let mut board = HexBoard::empty();
+          for (&hex, &value) in lane.iter().zip(&before) {
+            board.set(hex, value);
+          }
+          let mut expected = before.clone();
+          let expected_actions = shift_lane(&mut expected, &ClassicMerge);
+          let actions = board.shift(dir);
+          let after = lane
+            .iter()
+            .map(|&hex| board.get(hex).unwrap())
+            .collect::<Vec<_>>();
+          assert_eq!(after, expected, "{dir:?} {before:?}");
+          assert_eq!(actions.len(), expected_actions.len());
+          for (action, expected) in actions.iter().zip(expected_actions) {
+            assert_eq!(action.from, lane[expected.from]);
+            assert_eq!(action.to, lane[expected.to]);
+            assert_eq!(action.result_value, expected.result_value);
+          }
+          assert_eq!(
+            board.0.iter().filter(|n| **n != 0).count(),
+            after.iter().filter(|n| **n != 0).count()
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn shift() {
+    let mut board = HexBoard::empty();
+    board.set(Hex::new(-2, 0), 1);
+    board.set(Hex::new(0, 0), 1);
+    board.set(Hex::new(0, 2), 3);
+    let actions = board.shift(HexDirection::East);
+    assert_eq!(
+      actions,
+      vec![
+        HexAction {
+          kind: TileActionKind::Move,
+          moved_value: 1,
+          result_value: 1,
+          from: Hex::new(0, 0),
+          to: Hex::new(2, 0),
+        },
+        HexAction {
+          kind: TileActionKind::Merge,
+          moved_value: 1,
+          result_value: 2,
+          from: Hex::new(-2, 0),
+          to: Hex::new(2, 0),
+        },
+      ]
+    );
+    // already at the east edge of its row
+    assert_eq!(board.get(Hex::new(0, 2)), Some(3));
+    let actions = board.shift(HexDirection::NorthWest);
+    assert_eq!(actions.len(), 2);
+    assert_eq!(board.get(Hex::new(2, -2)), Some(2));
+    assert_eq!(board.get(Hex::new(0, -2)), Some(3));
+  }
+
+  #[test]
+  fn is_shiftable_matches_shifts() {
+    let mut rng = rand::rng();
+    for _ in 0..500 {
+      let mut board = HexBoard::empty();
+      for hex in HexBoard::cells() {
+        board.set(hex, rng.random_range(0..6));
+      }
+      let changes = HexDirection::ALL
+        .into_iter()
+        .any(|dir| !board.clone().shift(dir).is_empty());
+      assert_eq!(board.is_shiftable(), changes, "\n{board}");
+    }
+    let mut full = HexBoard::empty();
+    for (i, hex) in HexBoard::cells().enumerate() {
+      full.set(hex, i as u8 + 1);
+    }
+    assert!(!full.is_shiftable());
+    assert_eq!(full.spawn(), None);
+  }
+
+  #[test]
+  fn spawn() {
+    let mut board = HexBoard::empty();
+    for _ in 0..HexBoard::CELLS {
+      let (value, hex) = board.spawn().unwrap();
+      assert!([1, 2].contains(&value));
+      assert_eq!(board.get(hex), Some(value));
+    }
+    assert_eq!(board.spawn(), None);
+    assert_eq!(HexBoard::new().0.iter().filter(|n| **n != 0).count(), 2);
+  }
+
+  #[test]
+  fn display() {
+    let mut board = HexBoard::empty();
+    board.set(Hex::new(0, -2), 1);
+    board.set(Hex::new(0, 0), 11);
+    board.set(Hex::new(-2, 2), 3);
+    assert_eq!(
+      board.to_string(),
+      "  1 . .\n . . . .\n. . 11 . .\n . . . .\n  3 . .\n"
+    );
+  }
+}
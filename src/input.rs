@@ -0,0 +1,382 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::Direction;
+
+/// Modifier keys held along with another key. Left and right keys count the
+/// same.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Modifiers {
+  pub ctrl: bool,
+  pub shift: bool,
+  pub alt: bool,
+}
+
+impl Modifiers {
+  pub const NONE: Self = Self {
+    ctrl: false,
+    shift: false,
+    alt: false,
+  };
+  pub const CTRL: Self = Self {
+    ctrl: true,
+    ..Self::NONE
+  };
+  pub const SHIFT: Self = Self {
+    shift: true,
+    ..Self::NONE
+  };
+  pub const ALT: Self = Self {
+    alt: true,
+    ..Self::NONE
+  };
+
+  /// Returns the modifiers currently held on the keyboard.
+  pub fn held(keyboard_input: &ButtonInput<KeyCode>) -> Self {
+    Self {
+      ctrl: keyboard_input
+        .any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+      shift: keyboard_input
+        .any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+      alt: keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]),
+    }
+  }
+}
+
+/// A key pressed while holding exactly the given modifiers, e.g. Ctrl+R.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct KeyChord {
+  pub modifiers: Modifiers,
+  pub key: KeyCode,
+}
+
+impl KeyChord {
+  /// Creates a chord of a plain `key` without modifiers.
+  pub const fn new(key: KeyCode) -> Self {
+    Self {
+      modifiers: Modifiers::NONE,
+      key,
+    }
+  }
+
+  /// Creates a chord of `key` held with `modifiers`.
+  pub const fn with(modifiers: Modifiers, key: KeyCode) -> Self {
+    Self { modifiers, key }
+  }
+
+  /// Returns `true` if pressing `key` while holding `held` modifiers triggers
+  /// the chord. Extra held modifiers don't match, so Ctrl+R doesn't trigger
+  /// a plain R binding.
+  pub fn matches(&self, held: Modifiers, key: KeyCode) -> bool {
+    self.key == key && self.modifiers == held
+  }
+
+  /// Returns `true` if the chord has been pressed on this frame.
+  pub fn just_pressed(&self, keyboard_input: &ButtonInput<KeyCode>) -> bool {
+    keyboard_input.just_pressed(self.key)
+      && self.matches(Modifiers::held(keyboard_input), self.key)
+  }
+}
+
+/// Shows the chord the way keyboard shortcuts usually are, e.g. `Ctrl+R` or
+/// `ArrowUp`.
+impl std::fmt::Display for KeyChord {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let Modifiers { ctrl, shift, alt } = self.modifiers;
+    for (held, name) in [(ctrl, "Ctrl"), (shift, "Shift"), (alt, "Alt")] {
+      if held {
+        write!(f, "{name}+")?;
+      }
+    }
+    let key = format!("{:?}", self.key);
+    let key = key
+      .strip_prefix("Key")
+      .or_else(|| key.strip_prefix("Digit"))
+      .unwrap_or(&key);
+    write!(f, "{key}")
+  }
+}
+
+/// What the player asks the game to do, whichever device they ask with.
+/// Input systems only translate raw input into these, and the app can write
+/// them too.
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameAction {
+  /// Shifts the board to the given direction.
+  Shift(Direction),
+  /// Starts over the current game, or starts a new one once it's over.
+  Restart,
+  /// Goes on playing the current game after winning it.
+  KeepGoing,
+}
+
+impl GameAction {
+  /// Returns the direction of a [`Shift`](Self::Shift).
+  pub fn as_shift(&self) -> Option<Direction> {
+    match *self {
+      Self::Shift(dir) => Some(dir),
+      Self::Restart | Self::KeepGoing => None,
+    }
+  }
+}
+
+/// Keys the board reacts to.
+#[derive(Resource, Clone, Debug)]
+pub struct KeyBindings {
+  pub restart: KeyChord,
+  pub shifts: Vec<(KeyChord, Direction)>,
+}
+
+impl KeyBindings {
+  /// Returns every binding as the action it triggers.
+  pub fn actions(&self) -> impl Iterator<Item = (KeyChord, GameAction)> {
+    std::iter::once((self.restart, GameAction::Restart)).chain(
+      self
+        .shifts
+        .iter()
+        .map(|&(chord, dir)| (chord, GameAction::Shift(dir))),
+    )
+  }
+
+  /// Returns chords bound to more than one action, along with the first two
+  /// of them.
+  pub fn conflicts(&self) -> Vec<(KeyChord, GameAction, GameAction)> {
+    let actions = self.actions().collect::<Vec<_>>();
+    let mut conflicts: Vec<(KeyChord, GameAction, GameAction)> = Vec::new();
+    for (i, &(chord, first)) in actions.iter().enumerate() {
+      if conflicts.iter().any(|(c, ..)| *c == chord) {
+        continue;
+      }
+      if let Some(&(_, second)) = actions[i + 1..]
+        .iter()
+        .find(|(c, action)| *c == chord && *action != first)
+      {
+        conflicts.push((chord, first, second));
+      }
+    }
+    conflicts
+  }
+}
+
+impl Default for KeyBindings {
+  fn default() -> Self {
+    Self {
+      restart: KeyChord::new(KeyCode::KeyR),
+      shifts: [
+        (KeyCode::ArrowUp, Direction::Up),
+        (KeyCode::ArrowDown, Direction::Down),
+        (KeyCode::ArrowLeft, Direction::Left),
+        (KeyCode::ArrowRight, Direction::Right),
+        (KeyCode::KeyW, Direction::Up),
+        (KeyCode::KeyS, Direction::Down),
+        (KeyCode::KeyA, Direction::Left),
+        (KeyCode::KeyD, Direction::Right),
+      ]
+      .map(|(key, dir)| (KeyChord::new(key), dir))
+      .to_vec(),
+    }
+  }
+}
+
+/// Thresholds deciding whether a mouse drag or a touch swipe shifts the board.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct GestureSettings {
+  /// Shortest distance, in logical pixels, a swipe has to travel. Sensible
+  /// values lie between 20 and 100.
+  pub min_distance: f32,
+  /// Longest a swipe may take. Sensible values lie between 100 and 800 ms.
+  pub max_duration: Duration,
+  /// How many times longer a swipe has to be along one axis than along the
+  /// other to count as a swipe along it. Sensible values lie between 1.1 and
+  /// 2.0.
+  pub axis_bias: f32,
+}
+
+impl Default for GestureSettings {
+  fn default() -> Self {
+    Self {
+      min_distance: 40.0,
+      max_duration: Duration::from_millis(500),
+      axis_bias: 1.5,
+    }
+  }
+}
+
+/// Returns the direction of a swipe from `start` to `end` that took
+/// `duration`, if it is one according to `settings`. Positions are in window
+/// coordinates, with y pointing down.
+pub fn classify_swipe(
+  start: Vec2,
+  end: Vec2,
+  duration: Duration,
+  settings: &GestureSettings,
+) -> Option<Direction> {
+  let delta = end - start;
+  if duration > settings.max_duration || delta.length() < settings.min_distance
+  {
+    return None;
+  }
+  let (dx, dy) = (delta.x.abs(), delta.y.abs());
+  if dx >= dy * settings.axis_bias {
+    Some(if delta.x < 0.0 {
+      Direction::Left
+    } else {
+      Direction::Right
+    })
+  } else if dy >= dx * settings.axis_bias {
+    Some(if delta.y < 0.0 {
+      Direction::Up
+    } else {
+      Direction::Down
+    })
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn chord_matching() {
+    let plain = KeyChord::new(KeyCode::KeyR);
+    let ctrl = KeyChord::with(Modifiers::CTRL, KeyCode::KeyR);
+    let ctrl_shift = KeyChord::with(
+      Modifiers {
+        ctrl: true,
+        shift: true,
+        alt: false,
+      },
+      KeyCode::KeyR,
+    );
+    for (held, expected) in [
+      (Modifiers::NONE, [true, false, false]),
+      (Modifiers::CTRL, [false, true, false]),
+      (Modifiers::SHIFT, [false, false, false]),
+      (Modifiers::ALT, [false, false, false]),
+      (
+        Modifiers {
+          ctrl: true,
+          shift: true,
+          alt: false,
+        },
+        [false, false, true],
+      ),
+    ] {
+      let matched =
+        [plain, ctrl, ctrl_shift].map(|c| c.matches(held, KeyCode::KeyR));
+      assert_eq!(matched, expected, "{held:?}");
+    }
+    assert!(!plain.matches(Modifiers::NONE, KeyCode::KeyT));
+  }
+
+  #[test]
+  fn swipes() {
+    let settings = GestureSettings::default();
+    let fast = Duration::from_millis(200);
+    let swipe = |end: Vec2, duration, settings: &GestureSettings| {
+      classify_swipe(Vec2::new(100.0, 100.0), end, duration, settings)
+    };
+    for (end, expected) in [
+      (Vec2::new(20.0, 100.0), Direction::Left),
+      (Vec2::new(180.0, 110.0), Direction::Right),
+      (Vec2::new(100.0, 20.0), Direction::Up),
+      (Vec2::new(90.0, 180.0), Direction::Down),
+    ] {
+      assert_eq!(swipe(end, fast, &settings), Some(expected), "{end}");
+    }
+    // too short
+    assert_eq!(swipe(Vec2::new(130.0, 100.0), fast, &settings), None);
+    // too slow
+    let slow = Duration::from_millis(600);
+    assert_eq!(swipe(Vec2::new(20.0, 100.0), slow, &settings), None);
+    assert_eq!(
+      swipe(Vec2::new(20.0, 100.0), settings.max_duration, &settings),
+      Some(Direction::Left)
+    );
+  }
+
+  #[test]
+  fn diagonal_swipes() {
+    let swipe = |end: Vec2, axis_bias| {
+      let settings = GestureSettings {
+        axis_bias,
+        ..default()
+      };
+      classify_swipe(Vec2::ZERO, end, Duration::ZERO, &settings)
+    };
+    // a perfect diagonal is never a swipe
+    for bias in [1.1, 1.5, 2.0] {
+      assert_eq!(swipe(Vec2::new(60.0, 60.0), bias), None);
+      assert_eq!(swipe(Vec2::new(-60.0, 60.0), bias), None);
+    }
+    let slanted = Vec2::new(-60.0, -45.0);
+    assert_eq!(swipe(slanted, 1.1), Some(Direction::Left));
+    assert_eq!(swipe(slanted, 1.5), None);
+    let steep = Vec2::new(40.0, -90.0);
+    assert_eq!(swipe(steep, 2.0), Some(Direction::Up));
+    assert_eq!(swipe(steep, 2.5), None);
+  }
+
+  #[test]
+  fn binding_conflicts() {
+    let mut bindings = KeyBindings::default();
+    assert_eq!(bindings.actions().count(), 9);
+    assert!(bindings.conflicts().is_empty());
+    // binding the same action twice is fine
+    let up = KeyChord::new(KeyCode::ArrowUp);
+    bindings.shifts.push((up, Direction::Up));
+    assert!(bindings.conflicts().is_empty());
+    bindings.shifts.push((up, Direction::Down));
+    bindings.restart = KeyChord::new(KeyCode::KeyA);
+    assert_eq!(
+      bindings.conflicts(),
+      vec![
+        (
+          KeyChord::new(KeyCode::KeyA),
+          GameAction::Restart,
+          GameAction::Shift(Direction::Left)
+        ),
+        (
+          up,
+          GameAction::Shift(Direction::Up),
+          GameAction::Shift(Direction::Down)
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn chord_display() {
+    assert_eq!(KeyChord::new(KeyCode::KeyR).to_string(), "R");
+    assert_eq!(KeyChord::new(KeyCode::ArrowUp).to_string(), "ArrowUp");
+    assert_eq!(
+      KeyChord::with(Modifiers::CTRL, KeyCode::Digit1).to_string(),
+      "Ctrl+1"
+    );
+    let all = Modifiers {
+      ctrl: true,
+      shift: true,
+      alt: true,
+    };
+    assert_eq!(
+      KeyChord::with(all, KeyCode::Space).to_string(),
+      "Ctrl+Shift+Alt+Space"
+    );
+  }
+
+  #[test]
+  fn held_modifiers() {
+    let mut input = ButtonInput::<KeyCode>::default();
+    assert_eq!(Modifiers::held(&input), Modifiers::NONE);
+    input.press(KeyCode::ControlRight);
+    input.press(KeyCode::KeyR);
+    assert_eq!(Modifiers::held(&input), Modifiers::CTRL);
+    assert!(
+      KeyChord::with(Modifiers::CTRL, KeyCode::KeyR).just_pressed(&input)
+    );
+    assert!(!KeyChord::new(KeyCode::KeyR).just_pressed(&input));
+  }
+}
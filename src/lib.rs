@@ -1,82 +1,100 @@
-use bevy::{prelude::*, winit::WinitSettings};
-use board::BoardPlugin;
+//! 2048 the game: a [`Board`] implementing the rules, usable on its own, and
+//! a Bevy app playing on it, see [`AppPlugin`].
+//!
+//! The board's types derive [`Reflect`](bevy::reflect::Reflect), so even
+//! headless use depends on Bevy.
+//!
+//! The board can be played headlessly, without an app:
+//!
+//! ```
+//! use twenty_forty_eight::{Board, TileActionKind};
+//!
+//! let mut board = Board::<4>::new();
+//! let mut merges = 0;
+//! while let Some(&dir) = board.legal_moves().first() {
+//!   let actions = board.shift(dir);
+//!   merges += actions
+//!     .iter()
+//!     .filter(|a| a.kind == TileActionKind::Merge)
+//!     .count();
+//!   board.spawn();
+//! }
+//! assert!(!board.is_shiftable());
+//! assert!(merges > 0);
+//! ```
 
+use bevy::{prelude::*, ui::UiSystem, winit::WinitSettings};
+
+mod badges;
 mod board;
+#[cfg(all(feature = "debug-ui", debug_assertions))]
+mod debug;
+mod diagnostics;
 mod domain;
+mod input;
+mod overlay;
+mod rules;
+mod stats;
 mod style;
 
+pub use board::{
+  BoardPlugin, BoardScale, BoardSet, BoardSnapshot, BoardView, InputDebounce,
+  PendingMove, SpawnAdversary, SpawnAssist, StartingLayout,
+};
+#[cfg(feature = "experimental")]
+pub use domain::hex;
+pub use domain::{
+  Board, BoardError, ClassicMerge, DEFAULT_WIN_EXPONENT, Direction,
+  InitialLayout, LaneAction, LayoutError, MAX_EXPONENT, MergeRule,
+  OccupancyMask, ParseBoardError, ScoredBoard, Shiftability, SpawnBias,
+  SpawnPlacement, SpawnWeights, SpawnWeightsError, SquareBoard, TileAction,
+  TileActionKind, UndoableBoard, shift_lane,
+};
+pub use input::{
+  GameAction, GestureSettings, KeyBindings, KeyChord, Modifiers, classify_swipe,
+};
+pub use rules::{GameOverReason, GameOverRules, MoveCounters, WinTarget};
+pub use stats::{DirectionStats, StatsDelta};
+pub use style::GridStyle;
+
 pub struct AppPlugin;
 
 impl Plugin for AppPlugin {
   fn build(&self, app: &mut App) {
+    if let Some(initial_board) = board::InitialBoard::from_env() {
+      app.insert_resource(initial_board);
+    }
+    if let Some(spawn_rng) = board::SpawnRng::from_env() {
+      app.insert_resource(spawn_rng);
+    }
     app
       .insert_resource(WinitSettings::desktop_app())
-      .add_plugins((DefaultPlugins, BoardPlugin))
-      .init_state::<AppState>()
-      .add_systems(OnEnter(AppState::GameOver), show_game_over_overlay)
-      .add_systems(OnExit(AppState::GameOver), hide_game_over_overlay)
-      .add_systems(Update, handle_restart.run_if(in_state(AppState::GameOver)));
+      .add_plugins((
+        DefaultPlugins,
+        BoardPlugin::default(),
+        diagnostics::DiagnosticsOverlayPlugin,
+      ))
+      .add_systems(PostUpdate, style::scale_fonts.before(UiSystem::Layout));
+    #[cfg(all(feature = "debug-ui", debug_assertions))]
+    app.add_plugins(debug::DebugPlugin);
   }
 }
 
 #[derive(States, PartialEq, Eq, Clone, Copy, Hash, Default, Debug)]
-enum AppState {
+pub enum AppState {
   #[default]
   Playing,
   GameOver,
+  /// The [`WinTarget`] has been reached. The game can go on from here.
+  Won,
 }
 
-#[derive(Component)]
-struct GameOverOverlay;
-
-fn show_game_over_overlay(mut commands: Commands) {
-  commands.spawn((
-    GameOverOverlay,
-    Node {
-      width: Val::Percent(100.0),
-      max_width: Val::VMin(100.0),
-      aspect_ratio: Some(1.0),
-      flex_direction: FlexDirection::Column,
-      justify_content: JustifyContent::Center,
-      align_items: AlignItems::Center,
-      ..default()
-    },
-    BackgroundColor(style::GAME_OVER_BACKGROUND),
-    children![
-      (
-        Text::new("GAME OVER"),
-        TextLayout::new_with_justify(JustifyText::Center),
-        TextColor(style::TEXT_DARK),
-        TextFont {
-          font_size: 96.0,
-          ..default()
-        }
-      ),
-      (
-        Text::new("press any key to try again"),
-        TextLayout::new_with_justify(JustifyText::Center),
-        TextColor(style::TEXT_DARK),
-        TextFont {
-          font_size: 36.0,
-          ..default()
-        }
-      ),
-    ],
-  ));
-}
-
-fn handle_restart(
-  keyboard_input: Res<ButtonInput<KeyCode>>,
-  mut next_state: ResMut<NextState<AppState>>,
-) {
-  if keyboard_input.get_pressed().next().is_some() {
-    next_state.set(AppState::Playing);
-  }
-}
-
-fn hide_game_over_overlay(
-  query: Single<Entity, With<GameOverOverlay>>,
-  mut commands: Commands,
-) {
-  commands.entity(*query).despawn();
+/// Returns an entity with the given [`Name`], such as `"grid"`,
+/// `"tile-r2c3"` or `"overlay-gameover"`, for tests and UI automation to
+/// find what they look for without knowing its components.
+pub fn find_by_name(world: &mut World, name: &str) -> Option<Entity> {
+  world
+    .query::<(Entity, &Name)>()
+    .iter(world)
+    .find_map(|(entity, n)| (n.as_str() == name).then_some(entity))
 }
@@ -0,0 +1,231 @@
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use crate::{
+  BoardScale, Direction, DirectionStats, GameAction, GameOverReason,
+  KeyBindings, SpawnAdversary, SpawnAssist, WinTarget, board::CoversGrid,
+  style,
+};
+
+#[derive(Component)]
+pub(crate) struct GameOverOverlay;
+
+#[derive(Component)]
+pub(crate) struct WinOverlay;
+
+pub(crate) fn show_game_over_overlay(
+  reason: Option<Res<GameOverReason>>,
+  stats: Res<DirectionStats>,
+  scale: Res<BoardScale>,
+  assist: Option<Res<SpawnAssist>>,
+  adversary: Option<Res<SpawnAdversary>>,
+  mut commands: Commands,
+) {
+  let mut reason = reason.map(|r| r.to_string()).unwrap_or_default();
+  if adversary.is_some() {
+    reason.push_str(" (adversarial)");
+  } else if assist.is_some() {
+    reason.push_str(" (assisted)");
+  }
+  let mut node = Node {
+    flex_direction: FlexDirection::Column,
+    justify_content: JustifyContent::Center,
+    align_items: AlignItems::Center,
+    ..default()
+  };
+  scale.layout_cover(&mut node);
+  commands.spawn((
+    GameOverOverlay,
+    CoversGrid,
+    Name::new("overlay-gameover"),
+    node,
+    BackgroundColor(style::GAME_OVER_BACKGROUND),
+    children![
+      (
+        Name::new("gameover-title"),
+        Text::new("GAME OVER"),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextColor(style::TEXT_DARK),
+        style::TITLE_FONT,
+      ),
+      (
+        Name::new("gameover-reason"),
+        Text::new(reason),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextColor(style::TEXT_DARK),
+        style::SUBTITLE_FONT,
+      ),
+      (
+        Name::new("gameover-hint"),
+        Text::new("press any key to try again"),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextColor(style::TEXT_DARK),
+        style::SUBTITLE_FONT,
+      ),
+      direction_bars(&stats),
+    ],
+  ));
+}
+
+/// Four bars showing how many moves went to each direction, labeled with
+/// the moves' merges and score.
+fn direction_bars(stats: &DirectionStats) -> impl Bundle {
+  let most_moves = Direction::ALL
+    .map(|dir| stats.moves(dir))
+    .into_iter()
+    .max()
+    .unwrap_or_default()
+    .max(1);
+  let bars = Direction::ALL.map(|dir| {
+    let arrow = match dir {
+      Direction::Up => '↑',
+      Direction::Down => '↓',
+      Direction::Left => '←',
+      Direction::Right => '→',
+    };
+    let share = stats.moves(dir) as f32 / most_moves as f32;
+    (
+      Node {
+        width: Val::VMin(12.0),
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::End,
+        align_items: AlignItems::Center,
+        ..default()
+      },
+      children![
+        (
+          Node {
+            width: Val::VMin(6.0),
+            height: Val::VMin(15.0 * share),
+            ..default()
+          },
+          BackgroundColor(style::TEXT_DARK),
+        ),
+        (
+          Text::new(format!(
+            "{arrow} {}\n{} merges\n{} pts",
+            stats.moves(dir),
+            stats.merges(dir),
+            stats.score(dir)
+          )),
+          TextLayout::new_with_justify(JustifyText::Center),
+          TextColor(style::TEXT_DARK),
+          style::STATS_FONT,
+        ),
+      ],
+    )
+  });
+  (
+    Name::new("gameover-stats"),
+    Node {
+      margin: UiRect::top(Val::VMin(4.0)),
+      align_items: AlignItems::End,
+      column_gap: Val::VMin(2.0),
+      ..default()
+    },
+    Children::spawn(SpawnIter(bars.into_iter())),
+  )
+}
+
+pub(crate) fn handle_restart(
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  mut events: EventWriter<GameAction>,
+) {
+  if keyboard_input.get_pressed().next().is_some() {
+    events.write(GameAction::Restart);
+  }
+}
+
+pub(crate) fn hide_game_over_overlay(
+  query: Single<Entity, With<GameOverOverlay>>,
+  mut commands: Commands,
+) {
+  commands.entity(*query).despawn();
+}
+
+pub(crate) fn show_win_overlay(
+  target: Res<WinTarget>,
+  scale: Res<BoardScale>,
+  bindings: Option<Res<KeyBindings>>,
+  mut commands: Commands,
+) {
+  let tile = 1u64.checked_shl(target.0.into()).unwrap_or(u64::MAX);
+  // without input handling the app decides how to go on
+  let hint = bindings.map_or_else(String::new, |bindings| {
+    format!(
+      "press {} to start over\nor any other key to keep going",
+      bindings.restart
+    )
+  });
+  let mut node = Node {
+    flex_direction: FlexDirection::Column,
+    justify_content: JustifyContent::Center,
+    align_items: AlignItems::Center,
+    ..default()
+  };
+  scale.layout_cover(&mut node);
+  commands.spawn((
+    WinOverlay,
+    CoversGrid,
+    Name::new("overlay-win"),
+    node,
+    BackgroundColor(style::WIN_BACKGROUND),
+    children![
+      (
+        Name::new("win-title"),
+        Text::new("YOU WIN"),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextColor(style::TEXT_LIGHT),
+        style::TITLE_FONT,
+      ),
+      (
+        Name::new("win-reason"),
+        Text::new(format!("you made {tile}")),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextColor(style::TEXT_LIGHT),
+        style::SUBTITLE_FONT,
+      ),
+      (
+        Name::new("win-hint"),
+        Text::new(hint),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextColor(style::TEXT_LIGHT),
+        style::SUBTITLE_FONT,
+      ),
+    ],
+  ));
+}
+
+/// Starts over on the restart chord and keeps going on any other key but a
+/// modifier. Only keys pressed after winning count, so the one that made the
+/// winning move doesn't dismiss the overlay right away.
+pub(crate) fn handle_keep_going(
+  keyboard_input: Res<ButtonInput<KeyCode>>,
+  bindings: Res<KeyBindings>,
+  mut events: EventWriter<GameAction>,
+) {
+  use KeyCode::*;
+
+  if bindings.restart.just_pressed(&keyboard_input) {
+    events.write(GameAction::Restart);
+  } else if keyboard_input.get_just_pressed().any(|key| {
+    *key != bindings.restart.key
+      && !matches!(
+        key,
+        ControlLeft
+          | ControlRight
+          | ShiftLeft
+          | ShiftRight
+          | AltLeft
+          | AltRight
+      )
+  }) {
+    events.write(GameAction::KeepGoing);
+  }
+}
+
+pub(crate) fn hide_win_overlay(
+  query: Single<Entity, With<WinOverlay>>,
+  mut commands: Commands,
+) {
+  commands.entity(*query).despawn();
+}
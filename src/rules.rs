@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+
+use crate::domain::{Board, DEFAULT_WIN_EXPONENT, TileAction, TileActionKind};
+
+/// Optional ways for a game to end besides running out of moves.
+#[derive(Resource, Default, Clone, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct GameOverRules {
+  /// Ends the game once this many moves have been made.
+  pub max_moves: Option<u32>,
+  /// Ends the game after this many moves in a row without a merge.
+  pub sudden_death: Option<u32>,
+}
+
+/// Why the last game ended.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameOverReason {
+  /// The board can't be shifted anymore.
+  NoMoves,
+  /// [`GameOverRules::max_moves`] moves have been made.
+  MoveLimit(u32),
+  /// [`GameOverRules::sudden_death`] moves in a row didn't merge anything.
+  SuddenDeath(u32),
+}
+
+impl std::fmt::Display for GameOverReason {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NoMoves => write!(f, "no moves left"),
+      Self::MoveLimit(_) => write!(f, "out of moves"),
+      Self::SuddenDeath(n) => write!(f, "{n} merge-less moves"),
+    }
+  }
+}
+
+/// The tile exponent winning the game when it's first made, 11 for 2048 by
+/// default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct WinTarget(pub u8);
+
+impl Default for WinTarget {
+  fn default() -> Self {
+    Self(DEFAULT_WIN_EXPONENT)
+  }
+}
+
+/// Moves made in the current game, as counted for [`GameOverRules`].
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MoveCounters {
+  pub moves: u32,
+  /// Moves in a row, up to the last one, that didn't merge anything.
+  pub merge_less_streak: u32,
+}
+
+impl MoveCounters {
+  /// Accounts for a move that took `actions`.
+  pub fn record(&mut self, actions: &[TileAction]) {
+    self.moves += 1;
+    if actions.iter().any(|a| a.kind == TileActionKind::Merge) {
+      self.merge_less_streak = 0;
+    } else {
+      self.merge_less_streak += 1;
+    }
+  }
+}
+
+impl GameOverRules {
+  /// Returns why the game played on `board` with `counters` is over, if it
+  /// is. Running out of moves takes precedence over the optional rules.
+  pub fn check<const R: usize, const C: usize>(
+    &self,
+    board: &Board<R, C>,
+    counters: &MoveCounters,
+  ) -> Option<GameOverReason> {
+    if !board.is_shiftable() {
+      return Some(GameOverReason::NoMoves);
+    }
+    if let Some(max) = self.max_moves
+      && counters.moves >= max
+    {
+      return Some(GameOverReason::MoveLimit(max));
+    }
+    if let Some(streak) = self.sudden_death
+      && counters.merge_less_streak >= streak
+    {
+      return Some(GameOverReason::SuddenDeath(streak));
+    }
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn playable() -> Board<4> {
+    Board([
+      [1, 0, 0, 0], //
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+    ])
+  }
+
+  #[test]
+  fn move_limit() {
+    let rules = GameOverRules {
+      max_moves: Some(10),
+      ..default()
+    };
+    let counters = |moves| MoveCounters {
+      moves,
+      merge_less_streak: 0,
+    };
+    assert_eq!(rules.check(&playable(), &counters(9)), None);
+    assert_eq!(
+      rules.check(&playable(), &counters(10)),
+      Some(GameOverReason::MoveLimit(10))
+    );
+    assert_eq!(
+      GameOverRules::default().check(&playable(), &counters(1000)),
+      None
+    );
+  }
+
+  #[test]
+  fn sudden_death() {
+    let rules = GameOverRules {
+      sudden_death: Some(5),
+      ..default()
+    };
+    let mut counters = MoveCounters::default();
+    let moved = [TileAction::move_action(1, (0, 3), (0, 0))];
+    let merged = [TileAction::merge_action(1, (0, 3), (0, 0))];
+    for _ in 0..4 {
+      counters.record(&moved);
+    }
+    assert_eq!(rules.check(&playable(), &counters), None);
+    counters.record(&merged);
+    assert_eq!(counters.merge_less_streak, 0);
+    for _ in 0..4 {
+      counters.record(&moved);
+    }
+    assert_eq!(rules.check(&playable(), &counters), None);
+    counters.record(&moved);
+    assert_eq!(
+      rules.check(&playable(), &counters),
+      Some(GameOverReason::SuddenDeath(5))
+    );
+    assert_eq!(counters.moves, 10);
+    assert_eq!(
+      GameOverReason::SuddenDeath(5).to_string(),
+      "5 merge-less moves"
+    );
+  }
+
+  #[test]
+  fn no_moves_first() {
+    let rules = GameOverRules {
+      max_moves: Some(1),
+      sudden_death: Some(1),
+    };
+    let counters = MoveCounters {
+      moves: 1,
+      merge_less_streak: 1,
+    };
+    assert_eq!(
+      rules.check(&Board::<4>::fill_distinct(1), &counters),
+      Some(GameOverReason::NoMoves)
+    );
+  }
+}
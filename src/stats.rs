@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+
+use crate::domain::{Direction, TileAction, TileActionKind};
+
+/// Habits of the current game: how many moves were made to each direction,
+/// how many merges they produced and the score they earned. Reset when a new
+/// game starts.
+#[derive(Resource, Clone, PartialEq, Eq, Default, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct DirectionStats {
+  moves: [u32; 4],
+  merges: [u32; 4],
+  score: [u64; 4],
+}
+
+/// What a single move has added to [`DirectionStats`], so that it can be
+/// [rolled back](DirectionStats::rollback).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StatsDelta {
+  pub direction: Direction,
+  pub merges: u32,
+  pub score: u64,
+}
+
+impl DirectionStats {
+  /// Accounts for a move to `direction` that took `actions`. Returns what was
+  /// added to the stats.
+  pub fn record(
+    &mut self,
+    direction: Direction,
+    actions: &[TileAction],
+  ) -> StatsDelta {
+    let merges = actions.iter().filter(|a| a.kind == TileActionKind::Merge);
+    let delta = StatsDelta {
+      direction,
+      merges: merges.clone().count() as u32,
+      score: merges.map(|a| u64::from(a.points())).sum(),
+    };
+    let idx = direction as usize;
+    self.moves[idx] += 1;
+    self.merges[idx] += delta.merges;
+    self.score[idx] += delta.score;
+    delta
+  }
+
+  /// Takes back a move previously accounted for with
+  /// [`record`](Self::record).
+  pub fn rollback(&mut self, delta: StatsDelta) {
+    let idx = delta.direction as usize;
+    self.moves[idx] = self.moves[idx].saturating_sub(1);
+    self.merges[idx] = self.merges[idx].saturating_sub(delta.merges);
+    self.score[idx] = self.score[idx].saturating_sub(delta.score);
+  }
+
+  /// Returns the number of moves made to `direction`.
+  pub fn moves(&self, direction: Direction) -> u32 {
+    self.moves[direction as usize]
+  }
+
+  /// Returns the number of merges produced by moves to `direction`.
+  pub fn merges(&self, direction: Direction) -> u32 {
+    self.merges[direction as usize]
+  }
+
+  /// Returns the score earned by moves to `direction`.
+  pub fn score(&self, direction: Direction) -> u64 {
+    self.score[direction as usize]
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+  use super::*;
+  use crate::{
+    AppState, BoardPlugin, GameAction, board::BoardRes, domain::Board,
+  };
+
+  #[test]
+  fn record_and_rollback() {
+    let mut stats = DirectionStats::default();
+    let first = stats.record(
+      Direction::Left,
+      &[
+        TileAction::merge_action(1, (0, 1), (0, 0)),
+        TileAction::move_action(3, (0, 2), (0, 1)),
+        TileAction::merge_action(2, (1, 3), (1, 0)),
+      ],
+    );
+    assert_eq!(
+      first,
+      StatsDelta {
+        direction: Direction::Left,
+        merges: 2,
+        score: 4 + 8,
+      }
+    );
+    let second = stats.record(
+      Direction::Left,
+      &[TileAction::move_action(1, (2, 3), (2, 0))],
+    );
+    stats.record(
+      Direction::Up,
+      &[TileAction::merge_action(5, (3, 0), (0, 0))],
+    );
+    assert_eq!(stats.moves(Direction::Left), 2);
+    assert_eq!(stats.merges(Direction::Left), 2);
+    assert_eq!(stats.score(Direction::Left), 12);
+    assert_eq!(stats.moves(Direction::Up), 1);
+    assert_eq!(stats.score(Direction::Up), 64);
+    assert_eq!(stats.moves(Direction::Down), 0);
+
+    stats.rollback(second);
+    stats.rollback(first);
+    assert_eq!(stats.moves(Direction::Left), 0);
+    assert_eq!(stats.merges(Direction::Left), 0);
+    assert_eq!(stats.score(Direction::Left), 0);
+    assert_eq!(stats.moves(Direction::Up), 1);
+
+    // scores the way the game does, which saturates past the largest tile
+    let actions = [
+      TileAction::merge_action(31, (0, 1), (0, 0)),
+      TileAction::merge_action(40, (1, 1), (1, 0)),
+    ];
+    let delta = stats.record(Direction::Right, &actions);
+    assert_eq!(delta.score, 2 * u64::from(u32::MAX));
+  }
+
+  #[test]
+  fn scripted_game() {
+    let mut app = App::new();
+    app
+      .add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        BoardPlugin::default().without_input().without_overlays(),
+      ))
+      .insert_resource(TimeUpdateStrategy::ManualDuration(
+        Duration::from_millis(10),
+      ));
+    app.update();
+    app.world_mut().resource_mut::<BoardRes>().0 = Board([
+      [1, 1, 0, 0], //
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+      [0, 0, 0, 0],
+    ]);
+    // a lone tile on the top row can always go back and forth
+    for dir in [Direction::Left, Direction::Right, Direction::Left] {
+      app.world_mut().send_event(GameAction::Shift(dir));
+      for _ in 0..60 {
+        app.update();
+      }
+    }
+    let stats = app.world().resource::<DirectionStats>();
+    assert_eq!(stats.moves(Direction::Left), 2);
+    assert_eq!(stats.moves(Direction::Right), 1);
+    assert_eq!(stats.moves(Direction::Up) + stats.moves(Direction::Down), 0);
+    assert!(stats.merges(Direction::Left) >= 1);
+    assert!(stats.score(Direction::Left) >= 4);
+
+    app
+      .world_mut()
+      .resource_mut::<NextState<AppState>>()
+      .set(AppState::GameOver);
+    app.update();
+    app
+      .world_mut()
+      .resource_mut::<NextState<AppState>>()
+      .set(AppState::Playing);
+    app.update();
+    assert_eq!(
+      *app.world().resource::<DirectionStats>(),
+      DirectionStats::default()
+    );
+  }
+}
@@ -1,4 +1,4 @@
-use bevy::color::*;
+use bevy::{prelude::*, window::PrimaryWindow};
 
 pub const GRID: Color = Color::srgb_u8(187, 173, 160);
 
@@ -24,6 +24,20 @@ pub fn tile_foreground(n: u8) -> Color {
   *TILES.get(n as usize).unwrap_or(&DEFAULT_TILE)
 }
 
+/// Draws a soft shadow slightly below a tile. Offsets are relative to the
+/// tile's size and stay within the grid's padding.
+pub fn tile_shadow() -> BoxShadow {
+  BoxShadow::new(
+    TILE_SHADOW,
+    Val::Percent(0.0),
+    Val::Percent(3.0),
+    Val::Percent(0.0),
+    Val::Percent(6.0),
+  )
+}
+
+const TILE_SHADOW: Color = Color::srgba_u8(0x3C, 0x3A, 0x32, 0x60);
+
 pub const TEXT_LIGHT: Color = Color::srgb_u8(0xFC, 0xF4, 0xF0);
 pub const TEXT_DARK: Color = Color::srgb_u8(0x5C, 0x53, 0x4A);
 
@@ -33,3 +47,191 @@ pub fn tile_text(n: u8) -> Color {
 }
 
 pub const GAME_OVER_BACKGROUND: Color = Color::srgba_u8(0xEE, 0xEE, 0xEE, 0x50);
+pub const WIN_BACKGROUND: Color = Color::srgba_u8(0xED, 0xC2, 0x2E, 0x80);
+
+/// Optional looks of the grid. Changing it redraws the board.
+#[derive(Resource, Default, Clone, Debug, Reflect)]
+#[reflect(Resource, Default)]
+pub struct GridStyle {
+  /// Draws a soft shadow under each numbered tile.
+  pub tile_shadow: bool,
+  /// Fills the gaps between cells with this color instead of the grid's,
+  /// keeping the grid's color on its frame for a two-tone look.
+  pub gap_color: Option<Color>,
+  /// Briefly pushes the whole grid toward the direction of each shift.
+  pub nudge: bool,
+  /// Shows on each edge of the grid how many merges shifting toward it would
+  /// produce.
+  pub merge_badges: bool,
+}
+
+/// A font size given as a percentage of the window's smaller side, capped at
+/// `max` logical pixels.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(TextFont)]
+pub struct ResponsiveFont {
+  pub vmin: f32,
+  pub max: f32,
+}
+
+impl ResponsiveFont {
+  pub const fn new(vmin: f32, max: f32) -> Self {
+    Self { vmin, max }
+  }
+
+  /// Returns the font size for a window whose smaller side is `window_vmin`
+  /// logical pixels.
+  pub fn size(&self, window_vmin: f32) -> f32 {
+    (window_vmin * self.vmin / 100.0).min(self.max).max(1.0)
+  }
+}
+
+pub const TILE_FONT: ResponsiveFont = ResponsiveFont::new(7.0, 56.0);
+pub const TITLE_FONT: ResponsiveFont = ResponsiveFont::new(12.0, 96.0);
+pub const SUBTITLE_FONT: ResponsiveFont = ResponsiveFont::new(4.5, 36.0);
+pub const STATS_FONT: ResponsiveFont = ResponsiveFont::new(3.0, 24.0);
+pub const BADGE_FONT: ResponsiveFont = ResponsiveFont::new(2.2, 18.0);
+
+/// Keeps [`TextFont`] sizes of [`ResponsiveFont`] texts in sync with the
+/// primary window's size.
+pub fn scale_fonts(
+  window: Query<&Window, With<PrimaryWindow>>,
+  texts: Query<(&ResponsiveFont, &mut TextFont)>,
+) {
+  let Ok(window) = window.single() else {
+    return;
+  };
+  let window_vmin = window.width().min(window.height());
+  for (font, mut text_font) in texts {
+    let size = font.size(window_vmin);
+    if text_font.font_size != size {
+      text_font.font_size = size;
+    }
+  }
+}
+
+/// Returns the color `t` of the way from `from` to `to`, interpolated in
+/// Oklab, which keeps midpoints from turning muddy the way a lerp of sRGB
+/// components does.
+pub fn mix_oklab(from: Color, to: Color, t: f32) -> Color {
+  Oklaba::from(from).mix(&Oklaba::from(to), t).into()
+}
+
+/// Fades an entity's [`BackgroundColor`] and [`TextColor`], whichever it has,
+/// from one color to another with [`mix_oklab`]. Removed once done.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ColorTween {
+  from: Color,
+  to: Color,
+  duration: f32,
+  elapsed: f32,
+}
+
+impl ColorTween {
+  /// Creates a tween from `from` to `to` taking `duration` seconds.
+  pub fn new(from: Color, to: Color, duration: f32) -> Self {
+    Self {
+      from,
+      to,
+      duration,
+      elapsed: 0.0,
+    }
+  }
+
+  /// Advances the tween by `dt` seconds and returns the color at that point.
+  pub fn advance(&mut self, dt: f32) -> Color {
+    self.elapsed += dt;
+    mix_oklab(self.from, self.to, self.progress())
+  }
+
+  fn progress(&self) -> f32 {
+    if self.duration <= 0.0 {
+      return 1.0;
+    }
+    (self.elapsed / self.duration).min(1.0)
+  }
+
+  pub fn is_finished(&self) -> bool {
+    self.progress() >= 1.0
+  }
+}
+
+/// Advances [`ColorTween`]s and applies their colors.
+pub fn tween_colors(
+  time: Res<Time>,
+  tweens: Query<(
+    Entity,
+    &mut ColorTween,
+    Option<&mut BackgroundColor>,
+    Option<&mut TextColor>,
+  )>,
+  mut commands: Commands,
+) {
+  for (entity, mut tween, background, text) in tweens {
+    let color = tween.advance(time.delta_secs());
+    if let Some(mut background) = background {
+      background.0 = color;
+    }
+    if let Some(mut text) = text {
+      text.0 = color;
+    }
+    if tween.is_finished() {
+      commands.entity(entity).remove::<ColorTween>();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: Color, b: Color) {
+    let (a, b) = (a.to_linear(), b.to_linear());
+    let diff = a.to_vec4() - b.to_vec4();
+    assert!(diff.abs().max_element() < 1e-4, "{a:?} != {b:?}");
+  }
+
+  #[test]
+  fn oklab_mix() {
+    let (from, to) = (tile_foreground(1), tile_foreground(6));
+    assert_close(mix_oklab(from, to, 0.0), from);
+    assert_close(mix_oklab(from, to, 1.0), to);
+    let mid = Oklaba::from(mix_oklab(from, to, 0.5));
+    let (from, to) = (Oklaba::from(from), Oklaba::from(to));
+    assert!(
+      (mid.lightness - (from.lightness + to.lightness) / 2.0).abs() < 1e-4
+    );
+    assert!((mid.a - (from.a + to.a) / 2.0).abs() < 1e-4);
+    assert!((mid.b - (from.b + to.b) / 2.0).abs() < 1e-4);
+
+    // a plain sRGB lerp between complementary colors darkens the midpoint
+    let (red, cyan) = (Color::srgb(1.0, 0.0, 0.0), Color::srgb(0.0, 1.0, 1.0));
+    let lightness = |c: Color| Oklaba::from(c).lightness;
+    let srgb_mid = Srgba::from(red).mix(&Srgba::from(cyan), 0.5).into();
+    assert!(lightness(mix_oklab(red, cyan, 0.5)) > lightness(srgb_mid));
+  }
+
+  #[test]
+  fn color_tween() {
+    let (from, to) = (TEXT_DARK, TEXT_LIGHT);
+    let mut tween = ColorTween::new(from, to, 0.1);
+    assert_close(tween.advance(0.05), mix_oklab(from, to, 0.5));
+    assert!(!tween.is_finished());
+    assert_close(tween.advance(0.1), to);
+    assert!(tween.is_finished());
+    assert_close(ColorTween::new(from, to, 0.0).advance(0.0), to);
+  }
+
+  #[test]
+  fn responsive_font_size() {
+    let font = ResponsiveFont::new(10.0, 50.0);
+    assert_eq!(font.size(200.0), 20.0);
+    assert_eq!(font.size(500.0), 50.0);
+    assert_eq!(font.size(2000.0), 50.0);
+    assert_eq!(font.size(0.0), 1.0);
+    for font in [TILE_FONT, TITLE_FONT, SUBTITLE_FONT] {
+      assert_eq!(font.size(800.0), font.max);
+      assert!(font.size(100.0) < font.max);
+    }
+  }
+}